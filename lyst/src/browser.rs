@@ -0,0 +1,346 @@
+use std::{cmp, time::Duration};
+
+use lyst::{
+    font,
+    mohawk::{msnd::Sound, ResourceID, TypeID},
+    player::{self, Backend},
+};
+use sdl2::{
+    event::Event,
+    keyboard::Keycode,
+    pixels::{Color, PixelFormatEnum},
+    rect::Rect,
+    render::{BlendMode, Canvas, Texture, TextureCreator},
+    video::{Window, WindowContext},
+};
+
+const MIN_WIDTH: u32 = 320;
+const MIN_HEIGHT: u32 = 240;
+const MAX_SCALE: u32 = 8;
+const LINE_HEIGHT: u32 = font::GLYPH_SIZE + 2;
+
+/// Resource to browse, with its raw content
+pub struct Entry {
+    pub type_id: TypeID,
+    pub id: ResourceID,
+    pub name: Option<String>,
+    /// Content, or why it couldn't be read
+    pub data: Result<Vec<u8>, String>,
+}
+
+enum Decoded<'a> {
+    Picture {
+        texture: Texture<'a>,
+        width: u32,
+        height: u32,
+    },
+    Sound(Sound),
+    Unsupported,
+}
+
+fn decode<'a>(
+    texture_creator: &'a TextureCreator<WindowContext>,
+    entry: &Entry,
+) -> Result<Decoded<'a>, String> {
+    let data = entry
+        .data
+        .as_deref()
+        .map_err(|e| format!("unable to read: {}", e))?;
+
+    match entry.type_id {
+        TypeID::PICT => {
+            let image = pict_decoder::PICT::parse(data)
+                .and_then(|pict| pict.to_image())
+                .map_err(|e| e.to_string())?
+                .convert(pict_decoder::PixelFormat::RGB24);
+
+            let mut texture = texture_creator
+                .create_texture_streaming(
+                    PixelFormatEnum::RGB24,
+                    image.width() as u32,
+                    image.height() as u32,
+                )
+                .map_err(|e| e.to_string())?;
+            texture.with_lock(None, |buf, pitch| {
+                for (y, dst) in buf.chunks_mut(pitch).take(image.height()).enumerate() {
+                    let src = image.row(y);
+                    dst[..src.len()].copy_from_slice(src);
+                }
+            })?;
+            let query = texture.query();
+
+            Ok(Decoded::Picture {
+                texture,
+                width: query.width,
+                height: query.height,
+            })
+        }
+        TypeID::MSND => Sound::parse(data)
+            .map(Decoded::Sound)
+            .map_err(|e| e.to_string()),
+        TypeID::Unknown(_) => Ok(Decoded::Unsupported),
+    }
+}
+
+struct Browser<'a> {
+    entries: Vec<Entry>,
+    index: usize,
+    decoded: Result<Decoded<'a>, String>,
+    scale: u32,
+    show_overlay: bool,
+    jump_to: Option<String>,
+    message: Option<String>,
+}
+
+impl<'a> Browser<'a> {
+    fn select(&mut self, texture_creator: &'a TextureCreator<WindowContext>, index: usize) {
+        self.index = index;
+        self.decoded = decode(texture_creator, &self.entries[index]);
+    }
+
+    /// Find the resource with the given ID, preferring the currently shown type
+    fn find(&self, id: ResourceID) -> Option<usize> {
+        let current_type = &self.entries[self.index].type_id;
+
+        self.entries
+            .iter()
+            .position(|e| e.id == id && e.type_id == *current_type)
+            .or_else(|| self.entries.iter().position(|e| e.id == id))
+    }
+
+    fn window_size(&self) -> (u32, u32) {
+        match &self.decoded {
+            Ok(Decoded::Picture { width, height, .. }) => (
+                cmp::max(width * self.scale, MIN_WIDTH),
+                cmp::max(height * self.scale, MIN_HEIGHT),
+            ),
+            _ => (MIN_WIDTH, MIN_HEIGHT),
+        }
+    }
+
+    fn overlay(&self) -> Vec<(Color, String)> {
+        let white = Color::RGB(255, 255, 255);
+        let entry = &self.entries[self.index];
+
+        let mut ret = vec![(
+            white,
+            format!(
+                "{} {} {}  [{}/{}]  x{}",
+                entry.type_id,
+                entry.id,
+                entry.name.as_deref().unwrap_or(""),
+                self.index + 1,
+                self.entries.len(),
+                self.scale,
+            ),
+        )];
+
+        match &self.decoded {
+            Ok(Decoded::Picture { width, height, .. }) => {
+                ret.push((white, format!("{}x{}", width, height)))
+            }
+            Ok(Decoded::Sound(sound)) => ret.push((
+                white,
+                format!(
+                    "{:.2}s {}Hz {}ch, space to play",
+                    sound.duration().as_secs_f32(),
+                    sound.sample_rate,
+                    sound.channels,
+                ),
+            )),
+            Ok(Decoded::Unsupported) => ret.push((white, "unsupported type".to_string())),
+            Err(e) => ret.push((Color::RGB(255, 64, 64), format!("error: {}", e))),
+        }
+
+        if let Some(jump_to) = &self.jump_to {
+            ret.push((Color::RGB(255, 255, 0), format!("go to: {}_", jump_to)));
+        }
+        if let Some(message) = &self.message {
+            ret.push((Color::RGB(255, 255, 0), message.clone()));
+        }
+
+        ret
+    }
+
+    fn draw(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        canvas.set_draw_color(Color::RGB(32, 32, 32));
+        canvas.clear();
+
+        if let Ok(Decoded::Picture {
+            texture,
+            width,
+            height,
+        }) = &self.decoded
+        {
+            let (window_width, window_height) = canvas.output_size()?;
+            let (width, height) = (width * self.scale, height * self.scale);
+            canvas.copy(
+                texture,
+                None,
+                Rect::new(
+                    (window_width.saturating_sub(width) / 2) as i32,
+                    (window_height.saturating_sub(height) / 2) as i32,
+                    width,
+                    height,
+                ),
+            )?;
+        }
+
+        if self.show_overlay {
+            let lines = self.overlay();
+            let width = lines
+                .iter()
+                .map(|(_, line)| font::text_width(line))
+                .max()
+                .unwrap_or(0);
+
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, 192));
+            canvas.fill_rect(Rect::new(
+                0,
+                0,
+                width + 8,
+                lines.len() as u32 * LINE_HEIGHT + 4,
+            ))?;
+
+            for (i, (color, line)) in lines.iter().enumerate() {
+                let top = 3 + i as u32 * LINE_HEIGHT;
+                let pixels = font::text_pixels(line)
+                    .map(|(x, y)| Rect::new((4 + x) as i32, (top + y) as i32, 1, 1))
+                    .collect::<Vec<_>>();
+
+                canvas.set_draw_color(*color);
+                canvas.fill_rects(&pixels)?;
+            }
+        }
+
+        canvas.present();
+
+        Ok(())
+    }
+}
+
+/// Open a window to page through the given resources
+///
+/// Arrows, Home, End, PageUp and PageDown move around, `+` and `-` change the scale, typing an
+/// ID followed by Enter jumps to it, Space plays the shown sound, Tab toggles the overlay.
+pub fn run(title: &str, entries: Vec<Entry>, start: usize) -> Result<(), String> {
+    if entries.is_empty() {
+        return Err("nothing to browse".to_string());
+    }
+
+    let sdl_context = sdl2::init()?;
+    let video = sdl_context.video()?;
+    let mut mixer: Option<player::Mixer> = None;
+
+    let window = video
+        .window(&format!("lyst - {}", title), MIN_WIDTH, MIN_HEIGHT)
+        .position_centered()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    canvas.set_blend_mode(BlendMode::Blend);
+    let texture_creator = canvas.texture_creator();
+    video.text_input().start();
+
+    let mut browser = Browser {
+        entries,
+        index: start,
+        decoded: Ok(Decoded::Unsupported),
+        scale: 1,
+        show_overlay: true,
+        jump_to: None,
+        message: None,
+    };
+    browser.select(&texture_creator, start);
+
+    let mut event_pump = sdl_context.event_pump()?;
+    loop {
+        let (width, height) = browser.window_size();
+        if canvas.window().size() != (width, height) {
+            canvas
+                .window_mut()
+                .set_size(width, height)
+                .map_err(|e| e.to_string())?;
+        }
+        browser.draw(&mut canvas)?;
+
+        let count = browser.entries.len();
+        let mut select = None;
+        match event_pump.wait_event() {
+            Event::Quit { .. } => return Ok(()),
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } if browser.jump_to.is_none() => return Ok(()),
+            Event::TextInput { text, .. } => {
+                for c in text.chars() {
+                    match c {
+                        '0'..='9' => browser.jump_to.get_or_insert_with(String::new).push(c),
+                        '+' | '=' => browser.scale = cmp::min(browser.scale + 1, MAX_SCALE),
+                        '-' => browser.scale = cmp::max(browser.scale - 1, 1),
+                        _ => {}
+                    }
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => {
+                browser.message = None;
+
+                match keycode {
+                    Keycode::Right | Keycode::Down => select = Some((browser.index + 1) % count),
+                    Keycode::Left | Keycode::Up => {
+                        select = Some((browser.index + count - 1) % count)
+                    }
+                    Keycode::PageDown => select = Some(cmp::min(browser.index + 10, count - 1)),
+                    Keycode::PageUp => select = Some(browser.index.saturating_sub(10)),
+                    Keycode::Home => select = Some(0),
+                    Keycode::End => select = Some(count - 1),
+                    Keycode::Tab => browser.show_overlay = !browser.show_overlay,
+                    Keycode::Escape => browser.jump_to = None,
+                    Keycode::Backspace => {
+                        if let Some(jump_to) = &mut browser.jump_to {
+                            jump_to.pop();
+                        }
+                    }
+                    Keycode::Return | Keycode::KpEnter => {
+                        if let Some(jump_to) = browser.jump_to.take() {
+                            match jump_to.parse().ok().and_then(|id| browser.find(id)) {
+                                Some(index) => select = Some(index),
+                                None => browser.message = Some(format!("{} not found", jump_to)),
+                            }
+                        }
+                    }
+                    Keycode::Space => {
+                        if let Ok(Decoded::Sound(sound)) = &browser.decoded {
+                            if mixer.is_none() {
+                                match player::Mixer::open() {
+                                    Ok(opened) => mixer = Some(opened),
+                                    Err(e) => {
+                                        browser.message =
+                                            Some(format!("unable to open audio: {}", e))
+                                    }
+                                }
+                            }
+                            if let Some(mixer) = &mut mixer {
+                                if let Err(e) = mixer.play(sound, Duration::ZERO) {
+                                    browser.message = Some(format!("unable to play: {}", e));
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(index) = select {
+            if let Some(mixer) = &mut mixer {
+                mixer.stop();
+            }
+            browser.select(&texture_creator, index);
+        }
+    }
+}
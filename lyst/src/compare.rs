@@ -0,0 +1,110 @@
+use std::cmp;
+
+use image::{imageops, Rgb, RgbImage};
+
+/// Difference between a decoded image and its reference of the same size
+pub struct Comparison {
+    /// Count of pixels with at least one differing channel
+    pub mismatched: usize,
+    pub total: usize,
+    /// Peak signal-to-noise ratio in dB, infinite for identical images
+    pub psnr: f64,
+    /// Reference dimmed to gray, with differing pixels in red
+    pub diff: RgbImage,
+}
+
+/// Compare pixel by pixel, none if the dimensions differ
+pub fn compare(decoded: &RgbImage, reference: &RgbImage) -> Option<Comparison> {
+    if decoded.dimensions() != reference.dimensions() {
+        return None;
+    }
+
+    let mut mismatched = 0;
+    let mut squared_error = 0u64;
+    let mut diff = RgbImage::new(decoded.width(), decoded.height());
+    for ((got, expected), out) in decoded
+        .pixels()
+        .zip(reference.pixels())
+        .zip(diff.pixels_mut())
+    {
+        let mut max_delta = 0;
+        for (a, b) in got.0.iter().zip(expected.0) {
+            let delta = a.abs_diff(b);
+            max_delta = cmp::max(max_delta, delta);
+            squared_error += delta as u64 * delta as u64;
+        }
+
+        *out = if max_delta == 0 {
+            let gray = expected.0.iter().map(|&c| c as u32).sum::<u32>() / 3 / 4;
+            Rgb([gray as u8; 3])
+        } else {
+            mismatched += 1;
+            Rgb([cmp::max(max_delta, 128), 0, 0])
+        };
+    }
+
+    let samples = decoded.as_raw().len() as f64;
+    let psnr = match squared_error {
+        0 => f64::INFINITY,
+        _ => 10.0 * (255.0 * 255.0 / (squared_error as f64 / samples)).log10(),
+    };
+
+    Some(Comparison {
+        mismatched,
+        total: decoded.pixels().len(),
+        psnr,
+        diff,
+    })
+}
+
+/// Place both images next to each other, for when they can't be compared
+pub fn side_by_side(left: &RgbImage, right: &RgbImage) -> RgbImage {
+    let mut ret = RgbImage::new(
+        left.width() + right.width(),
+        cmp::max(left.height(), right.height()),
+    );
+    imageops::replace(&mut ret, left, 0, 0);
+    imageops::replace(&mut ret, right, left.width() as i64, 0);
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical() {
+        let image = RgbImage::from_pixel(4, 2, Rgb([200, 100, 0]));
+
+        let comparison = compare(&image, &image).expect("same dimensions");
+
+        assert_eq!(comparison.mismatched, 0);
+        assert_eq!(comparison.total, 8);
+        assert!(comparison.psnr.is_infinite());
+        assert_eq!(*comparison.diff.get_pixel(0, 0), Rgb([25, 25, 25]));
+    }
+
+    #[test]
+    fn one_pixel_off() {
+        let reference = RgbImage::new(2, 2);
+        let mut decoded = reference.clone();
+        decoded.put_pixel(1, 0, Rgb([0, 0, 255]));
+
+        let comparison = compare(&decoded, &reference).expect("same dimensions");
+
+        assert_eq!(comparison.mismatched, 1);
+        // MSE of 255² over 12 samples
+        assert!((comparison.psnr - 10.0 * 12f64.log10()).abs() < 1e-9);
+        assert_eq!(*comparison.diff.get_pixel(1, 0), Rgb([255, 0, 0]));
+        assert_eq!(*comparison.diff.get_pixel(0, 0), Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn different_dimensions() {
+        let (a, b) = (RgbImage::new(2, 3), RgbImage::new(3, 2));
+
+        assert!(compare(&a, &b).is_none());
+        assert_eq!(side_by_side(&a, &b).dimensions(), (5, 3));
+    }
+}
@@ -0,0 +1,134 @@
+use std::cmp;
+
+use image::{
+    imageops::{self, FilterType},
+    Rgb, RgbImage,
+};
+
+use crate::{font, mohawk::ResourceID};
+
+pub const THUMBNAIL_SIZE: u32 = 128;
+const PADDING: u32 = 4;
+const LINE_HEIGHT: u32 = font::GLYPH_SIZE + 2;
+const LABEL_LINES: u32 = 2;
+const CELL_WIDTH: u32 = THUMBNAIL_SIZE + 2 * PADDING;
+const CELL_HEIGHT: u32 = THUMBNAIL_SIZE + 2 * PADDING + LABEL_LINES * LINE_HEIGHT;
+
+const BACKGROUND: Rgb<u8> = Rgb([32, 32, 32]);
+const TEXT: Rgb<u8> = Rgb([255, 255, 255]);
+const ERROR: Rgb<u8> = Rgb([255, 64, 64]);
+
+/// Picture to place on the sheet, or the reason it couldn't be decoded
+pub struct Cell {
+    pub id: ResourceID,
+    pub name: Option<String>,
+    pub image: Result<RgbImage, String>,
+}
+
+/// Downscale to fit in a thumbnail, keeping the aspect ratio; smaller images are kept as is
+pub fn thumbnail(image: &RgbImage) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let longest = cmp::max(width, height);
+    if longest <= THUMBNAIL_SIZE {
+        return image.clone();
+    }
+
+    let scaled = |side: u32| cmp::max(1, side * THUMBNAIL_SIZE / longest);
+    imageops::resize(image, scaled(width), scaled(height), FilterType::Lanczos3)
+}
+
+/// Draw text, cut to the width of a thumbnail
+fn draw_text(sheet: &mut RgbImage, x: u32, y: u32, text: &str, color: Rgb<u8>) {
+    let text: String = text
+        .chars()
+        .take((THUMBNAIL_SIZE / font::GLYPH_SIZE) as usize)
+        .collect();
+
+    for (dx, dy) in font::text_pixels(&text) {
+        sheet.put_pixel(x + dx, y + dy, color);
+    }
+}
+
+/// Lay out the cells in a grid, each thumbnail with its ID and name under it
+pub fn render(cells: &[Cell], columns: u32) -> RgbImage {
+    let columns = columns.clamp(1, cmp::max(cells.len() as u32, 1));
+    let rows = (cells.len() as u32).div_ceil(columns);
+
+    let mut sheet = RgbImage::from_pixel(columns * CELL_WIDTH, rows * CELL_HEIGHT, BACKGROUND);
+    for (i, cell) in cells.iter().enumerate() {
+        let x = (i as u32 % columns) * CELL_WIDTH + PADDING;
+        let y = (i as u32 / columns) * CELL_HEIGHT + PADDING;
+
+        match &cell.image {
+            Ok(image) => {
+                let thumbnail = thumbnail(image);
+                imageops::replace(
+                    &mut sheet,
+                    &thumbnail,
+                    (x + (THUMBNAIL_SIZE - thumbnail.width()) / 2) as i64,
+                    (y + (THUMBNAIL_SIZE - thumbnail.height()) / 2) as i64,
+                );
+            }
+            Err(_) => draw_text(
+                &mut sheet,
+                x,
+                y + THUMBNAIL_SIZE / 2,
+                "unable to decode",
+                ERROR,
+            ),
+        }
+
+        let label_y = y + THUMBNAIL_SIZE + PADDING;
+        draw_text(&mut sheet, x, label_y, &cell.id.to_string(), TEXT);
+        if let Some(name) = &cell.name {
+            draw_text(&mut sheet, x, label_y + LINE_HEIGHT, name, TEXT);
+        }
+    }
+
+    sheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_grid() {
+        let cells = [
+            Cell {
+                id: 1,
+                name: Some("a very long name, longer than the thumbnail".to_string()),
+                image: Ok(RgbImage::from_pixel(512, 256, Rgb([0, 255, 0]))),
+            },
+            Cell {
+                id: 2,
+                name: None,
+                image: Err("broken".to_string()),
+            },
+            Cell {
+                id: 3,
+                name: None,
+                image: Ok(RgbImage::from_pixel(2, 2, Rgb([0, 0, 255]))),
+            },
+        ];
+
+        let sheet = render(&cells, 2);
+
+        assert_eq!(sheet.dimensions(), (2 * CELL_WIDTH, 2 * CELL_HEIGHT));
+        // downscaled to 128x64, centered vertically
+        let center = PADDING + THUMBNAIL_SIZE / 2;
+        assert_eq!(*sheet.get_pixel(PADDING, center), Rgb([0, 255, 0]));
+        assert_eq!(*sheet.get_pixel(PADDING, PADDING + 8), BACKGROUND);
+        // kept at its size, centered
+        let (x, y) = (PADDING + 63, CELL_HEIGHT + PADDING + 63);
+        assert_eq!(*sheet.get_pixel(x, y), Rgb([0, 0, 255]));
+        assert_eq!(*sheet.get_pixel(x - 1, y), BACKGROUND);
+    }
+
+    #[test]
+    fn thumbnail_keeps_ratio() {
+        let image = RgbImage::new(300, 1000);
+
+        assert_eq!(thumbnail(&image).dimensions(), (38, 128));
+    }
+}
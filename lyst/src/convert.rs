@@ -0,0 +1,74 @@
+use std::io::Cursor;
+
+use image::{codecs::png::PngEncoder, ColorType, ImageEncoder, RgbImage};
+
+use pict_decoder::PixelFormat;
+
+use crate::mohawk::{msnd, TypeID};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("parse pict: {0}")]
+    PICT(#[from] pict_decoder::Error),
+    #[error("parse msnd: {0}")]
+    MSND(#[from] msnd::Error),
+    #[error("encode png: {0}")]
+    PNG(#[from] image::ImageError),
+    #[error("decoded picture doesn't match its size")]
+    PictureSize,
+}
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub struct Converted {
+    pub extension: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// Decode a raw picture to its pixels
+pub fn decode_picture(raw: &[u8]) -> Result<RgbImage> {
+    let image = pict_decoder::PICT::parse(raw)?
+        .to_image()?
+        .convert(PixelFormat::RGB24);
+
+    RgbImage::from_raw(
+        image.width() as u32,
+        image.height() as u32,
+        image.into_data(),
+    )
+    .ok_or(Error::PictureSize)
+}
+
+/// Convert a raw resource to a common format, unknown types are kept as is
+pub fn convert(type_id: &TypeID, raw: Vec<u8>) -> Result<Converted> {
+    match type_id {
+        TypeID::PICT => {
+            let image = pict_decoder::PICT::parse(raw.as_slice())?.to_image()?;
+            // keep transparency when there is any
+            let (image, color_type) = match image.format() {
+                PixelFormat::RGBA32 => (image, ColorType::Rgba8),
+                _ => (image.convert(PixelFormat::RGB24), ColorType::Rgb8),
+            };
+
+            let mut png = Vec::new();
+            PngEncoder::new(Cursor::new(&mut png)).write_image(
+                image.data(),
+                image.width() as u32,
+                image.height() as u32,
+                color_type,
+            )?;
+
+            Ok(Converted {
+                extension: "png",
+                data: png,
+            })
+        }
+        TypeID::MSND => Ok(Converted {
+            extension: "wav",
+            data: msnd::Sound::parse(raw.as_slice())?.to_wav(),
+        }),
+        TypeID::Unknown(_) => Ok(Converted {
+            extension: "bin",
+            data: raw,
+        }),
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tokio::{
+    io::{self, AsyncReadExt},
+    sync::Semaphore,
+    task::{self, JoinSet},
+};
+use tracing::warn;
+
+use crate::{convert, mohawk, Mohawk};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("open: {0}")]
+    Open(mohawk::Error),
+    #[error("read: {0}")]
+    Read(#[from] mohawk::Error),
+    #[error("convert: {0}")]
+    Convert(#[from] convert::Error),
+    #[error("write: {0}")]
+    Write(#[from] io::Error),
+}
+
+/// Outcome of extracting an install
+pub struct Summary {
+    pub extracted: usize,
+    /// Archive or resource which failed, sorted by location, an archive counting once
+    pub failures: Vec<(String, Error)>,
+}
+
+/// Replace characters which are unsafe in a filename
+fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Convert the resource and write it to the directory
+fn write_converted(
+    type_id: &mohawk::TypeID,
+    raw: Vec<u8>,
+    dir: &Path,
+    filename: &str,
+) -> Result<(), Error> {
+    let converted = convert::convert(type_id, raw)?;
+
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(format!("{}.{}", filename, converted.extension)),
+        converted.data,
+    )?;
+
+    Ok(())
+}
+
+/// Write every resource of the archives to the output, by archive then type, reading and
+/// converting up to `jobs` resources at once and continuing past failures
+pub async fn extract_all(archives: Vec<PathBuf>, output: &Path, jobs: usize) -> Summary {
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut tasks = JoinSet::new();
+    let mut ret = Summary {
+        extracted: 0,
+        failures: Vec::new(),
+    };
+
+    for archive in archives {
+        let archive_name = archive
+            .file_stem()
+            .unwrap_or(archive.as_os_str())
+            .to_string_lossy()
+            .into_owned();
+
+        let mohawk = match Mohawk::open(&archive).await {
+            Ok(mohawk) => mohawk,
+            Err(e) => {
+                warn!("skip {}: {}", archive.display(), e);
+                ret.failures.push((archive_name, Error::Open(e)));
+                continue;
+            }
+        };
+
+        let mut sorted_types: Vec<_> = mohawk.types.iter().collect();
+        sorted_types.sort_unstable_by_key(|(t, _)| *t);
+        for (type_id, resources) in sorted_types {
+            let dir = output.join(&archive_name).join(type_id.to_string());
+
+            let mut sorted_resources: Vec<_> = resources.iter().collect();
+            sorted_resources.sort_unstable_by_key(|(id, _)| *id);
+            for (resource_id, resource) in sorted_resources {
+                let location = format!("{}/{}/{}", archive_name, type_id, resource_id);
+                let mut filename = resource_id.to_string();
+                if let Some(name) = &resource.name {
+                    filename.push('-');
+                    filename.push_str(&sanitize_filename(name));
+                }
+
+                // acquire before reading to also bound memory usage
+                let permit = semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore to never be closed");
+                let mut reader = resource.reader();
+                let size = resource.file.size as usize;
+                let type_id = type_id.clone();
+                let dir = dir.clone();
+
+                tasks.spawn(async move {
+                    let _permit = permit;
+
+                    let mut raw = Vec::with_capacity(size);
+                    let extracted = match reader.read_to_end(&mut raw).await {
+                        Ok(_) => task::spawn_blocking(move || {
+                            write_converted(&type_id, raw, &dir, &filename)
+                        })
+                        .await
+                        .expect("conversion to not panic"),
+                        Err(e) => Err(Error::Read(e.into())),
+                    };
+
+                    (location, extracted)
+                });
+            }
+        }
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        match joined.expect("extract task to not be cancelled") {
+            (_, Ok(())) => ret.extracted += 1,
+            (location, Err(e)) => ret.failures.push((location, e)),
+        }
+    }
+    ret.failures.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{build_mohawk, BuiltResource};
+
+    #[test_log::test(tokio::test)]
+    async fn continue_past_failures() {
+        let dir = tempfile::tempdir().expect("to create temporary directory");
+        let path = dir.path().join("TEST.DAT");
+        std::fs::write(
+            &path,
+            build_mohawk(&[
+                BuiltResource {
+                    type_id: *b"PICT",
+                    id: 1,
+                    name: None,
+                    data: b"not a picture",
+                },
+                BuiltResource {
+                    type_id: *b"tBMP",
+                    id: 2,
+                    name: Some("a/b"),
+                    data: b"raw",
+                },
+            ]),
+        )
+        .expect("to write archive");
+        let missing = dir.path().join("MISSING.DAT");
+        let output = dir.path().join("out");
+
+        let summary = extract_all(vec![missing, path], &output, 2).await;
+
+        assert_eq!(summary.extracted, 1);
+        let failed: Vec<_> = summary.failures.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(failed, ["MISSING", "TEST/PICT/1"]);
+        assert!(matches!(summary.failures[0].1, Error::Open(_)));
+        assert!(matches!(summary.failures[1].1, Error::Convert(_)));
+
+        let extracted = output.join("TEST").join("tBMP").join("2-a_b.bin");
+        assert_eq!(std::fs::read(extracted).expect("to be written"), b"raw");
+    }
+}
@@ -0,0 +1,41 @@
+use font8x8::{UnicodeFonts, BASIC_FONTS, LATIN_FONTS};
+
+/// Width and height of a character
+pub const GLYPH_SIZE: u32 = 8;
+
+fn glyph(c: char) -> [u8; 8] {
+    BASIC_FONTS
+        .get(c)
+        .or_else(|| LATIN_FONTS.get(c))
+        .or_else(|| BASIC_FONTS.get('?'))
+        .expect("'?' to be in basic font")
+}
+
+/// Coordinates of the lit pixels of a single line of text
+pub fn text_pixels(text: &str) -> impl Iterator<Item = (u32, u32)> + '_ {
+    text.chars().enumerate().flat_map(|(column, c)| {
+        glyph(c).into_iter().enumerate().flat_map(move |(y, row)| {
+            (0..GLYPH_SIZE)
+                .filter(move |x| row & (1 << x) != 0)
+                .map(move |x| (column as u32 * GLYPH_SIZE + x, y as u32))
+        })
+    })
+}
+
+/// Size in pixels of a single line of text
+pub fn text_width(text: &str) -> u32 {
+    text.chars().count() as u32 * GLYPH_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_are_inside_text_box() {
+        let text = "ID 42 é";
+
+        assert!(text_pixels(text).all(|(x, y)| x < text_width(text) && y < GLYPH_SIZE));
+        assert_eq!(text_pixels(" ").count(), 0);
+    }
+}
@@ -0,0 +1,27 @@
+use std::path::{Path, PathBuf};
+
+use tokio::{fs, io};
+
+/// List Mohawk archives at given path, either the file itself or every `.DAT` of a directory
+pub async fn find_archives(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+
+    if !fs::metadata(path).await?.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut ret = Vec::new();
+    let mut entries = fs::read_dir(path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let is_archive = entry
+            .path()
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("dat"));
+        if is_archive && entry.file_type().await?.is_file() {
+            ret.push(entry.path());
+        }
+    }
+    ret.sort_unstable();
+
+    Ok(ret)
+}
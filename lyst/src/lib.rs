@@ -1,4 +1,14 @@
+pub mod compare;
+pub mod contact_sheet;
+pub mod convert;
+pub mod extract;
+pub mod font;
+pub mod install;
 pub mod mohawk;
+pub mod player;
+pub mod search;
+pub mod server;
+pub mod verify;
 pub use mohawk::Mohawk;
 
 #[derive(thiserror::Error, Debug)]
@@ -1,23 +1,31 @@
 use lyst::{
     mohawk::{Resource, ResourceID, TypeID},
+    player::{self, Backend, Control, Player},
+    search::{self, Pattern},
     Mohawk,
 };
-use sdl2::{image::LoadTexture, pixels::PixelFormatEnum};
 use std::{
     collections::HashMap,
+    fs,
+    net::Ipv4Addr,
     path::{Path, PathBuf},
     process::ExitCode,
     result,
+    sync::mpsc,
+    thread,
     time::Duration,
 };
 
 use tokio::{
     io::{self, stdout},
+    net::TcpListener,
     task::spawn_blocking,
 };
 
 use clap::{Parser, Subcommand};
 
+mod browser;
+
 fn is_4_chars(arg: &str) -> result::Result<TypeID, String> {
     let raw: [u8; 4] = arg
         .bytes()
@@ -39,25 +47,123 @@ struct Interface {
 enum Commands {
     /// List content of given Mohawk file
     List { path: PathBuf },
-    /// List content of given Mohawk file
+    /// Show header and statistics of given Mohawk file
+    Info { path: PathBuf },
+    /// Write a single sound of given Mohawk file as is to stdout, or show a single picture in a
+    /// window
     Extract {
         path: PathBuf,
         #[arg(value_parser = is_4_chars)]
         type_id: TypeID,
         resource_id: ResourceID,
     },
+    /// Page through pictures and sounds of given Mohawk file in a window
+    Browse { path: PathBuf },
+    /// Play a sound, reading controls from stdin: `N` seeks to N seconds, `+N` and `-N` move by N
+    /// seconds, `stop` stops
+    Play {
+        path: PathBuf,
+        resource_id: ResourceID,
+        /// Start position, in seconds
+        #[arg(long, default_value_t = 0.0)]
+        start: f64,
+        /// Do not output any sound, only go through the motions
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List opcodes of a picture, with their offset and decoded fields
+    PictDump {
+        path: PathBuf,
+        resource_id: ResourceID,
+    },
+    /// Search resources of given Mohawk files or install directories
+    Grep {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Resource name, as a glob
+        #[arg(long)]
+        name: Option<String>,
+        /// Resource name, as a regex
+        #[arg(long)]
+        name_regex: Option<String>,
+        /// Raw content, as hex bytes with `??` matching any byte
+        #[arg(long)]
+        bytes: Option<String>,
+        /// Decoded text, such as picture comments, as a regex
+        #[arg(long)]
+        text: Option<String>,
+    },
+    /// Browse given Mohawk file or install directory from a web browser, on localhost
+    Serve {
+        path: PathBuf,
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Compare decoded pictures of given Mohawk file to reference PNGs named by ID
+    Compare {
+        path: PathBuf,
+        reference_dir: PathBuf,
+        /// Where to write the diff images of failures
+        #[arg(long, default_value = "diff")]
+        diff_dir: PathBuf,
+    },
+    /// Render thumbnails of every picture of given Mohawk file in a labelled grid
+    ContactSheet {
+        path: PathBuf,
+        #[arg(long)]
+        out: PathBuf,
+        /// Thumbnails per row
+        #[arg(long, default_value_t = 8)]
+        columns: u32,
+    },
+    /// Decode every supported resource of given install directory, reporting failures
+    VerifyInstall {
+        path: PathBuf,
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Extract every resource of given Mohawk file or install directory, converting known types
+    ExtractAll {
+        path: PathBuf,
+        output: PathBuf,
+        /// Maximum number of resources decoded at once, defaults to the number of CPUs
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
 }
 
 mod errors {
-    use lyst::mohawk;
+    use lyst::{mohawk, search};
+    use std::time::TryFromFloatSecsError;
     use tokio::{io, task};
 
     #[derive(thiserror::Error, Debug)]
     pub enum Error {
         #[error("list: {0}")]
         List(#[from] ListError),
+        #[error("info: {0}")]
+        Info(#[from] InfoError),
         #[error("extract: {0}")]
         Extract(#[from] ExtractError),
+        #[error("extract all: {0}")]
+        ExtractAll(#[from] ExtractAllError),
+        #[error("pict dump: {0}")]
+        PictDump(#[from] PictDumpError),
+        #[error("browse: {0}")]
+        Browse(#[from] BrowseError),
+        #[error("play: {0}")]
+        Play(#[from] PlayError),
+        #[error("grep: {0}")]
+        Grep(#[from] GrepError),
+        #[error("verify install: {0}")]
+        VerifyInstall(#[from] VerifyInstallError),
+        #[error("contact sheet: {0}")]
+        ContactSheet(#[from] ContactSheetError),
+        #[error("serve: {0}")]
+        Serve(#[from] ServeError),
+        #[error("compare: {0}")]
+        Compare(#[from] CompareError),
     }
 
     #[derive(thiserror::Error, Debug)]
@@ -66,6 +172,12 @@ mod errors {
         Mohawk(#[from] mohawk::Error),
     }
 
+    #[derive(thiserror::Error, Debug)]
+    pub enum InfoError {
+        #[error(transparent)]
+        Mohawk(#[from] mohawk::Error),
+    }
+
     #[derive(thiserror::Error, Debug)]
     pub enum ExtractError {
         #[error(transparent)]
@@ -84,6 +196,109 @@ mod errors {
         #[error("show extracted picture: {0}")]
         ShowPict(String),
     }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum BrowseError {
+        #[error(transparent)]
+        Mohawk(#[from] mohawk::Error),
+
+        #[error("setup browser: {0}")]
+        SetupBrowser(task::JoinError),
+        #[error("browser: {0}")]
+        Browser(String),
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum PlayError {
+        #[error(transparent)]
+        Mohawk(#[from] mohawk::Error),
+
+        #[error("resource not found")]
+        ResourceNotFound,
+        #[error("invalid start position: {0}")]
+        InvalidStart(TryFromFloatSecsError),
+        #[error("setup player: {0}")]
+        SetupPlayer(task::JoinError),
+        #[error("playback: {0}")]
+        Playback(String),
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum GrepError {
+        #[error("invalid pattern: {0}")]
+        Pattern(#[from] search::Error),
+        #[error("no pattern given")]
+        NoPattern,
+        #[error("find archives: {0}")]
+        FindArchives(io::Error),
+        #[error("{0} archives couldn't be searched")]
+        Failures(usize),
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum CompareError {
+        #[error(transparent)]
+        Mohawk(#[from] mohawk::Error),
+
+        #[error("no picture found")]
+        NoPicture,
+        #[error("create diff directory: {0}")]
+        CreateDiffDir(io::Error),
+        #[error("setup comparison: {0}")]
+        SetupCompare(task::JoinError),
+        #[error("{0} pictures differ from their reference")]
+        Failures(usize),
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum ServeError {
+        #[error("find archives: {0}")]
+        FindArchives(io::Error),
+        #[error("bind: {0}")]
+        Bind(io::Error),
+        #[error("server: {0}")]
+        Server(io::Error),
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum ContactSheetError {
+        #[error(transparent)]
+        Mohawk(#[from] mohawk::Error),
+
+        #[error("no picture found")]
+        NoPicture,
+        #[error("setup rendering: {0}")]
+        SetupRender(task::JoinError),
+        #[error("write sheet: {0}")]
+        Write(#[from] image::ImageError),
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum VerifyInstallError {
+        #[error("find archives: {0}")]
+        FindArchives(io::Error),
+        #[error("write json: {0}")]
+        Json(#[from] serde_json::Error),
+        #[error("{0} resources failed to decode")]
+        Failures(usize),
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum PictDumpError {
+        #[error(transparent)]
+        Mohawk(#[from] mohawk::Error),
+
+        #[error("resource not found")]
+        ResourceNotFound,
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum ExtractAllError {
+        #[error("find archives: {0}")]
+        FindArchives(io::Error),
+        #[error("{0} resources failed to extract")]
+        Failures(usize),
+    }
 }
 
 async fn list(path: &Path) -> Result<(), errors::ListError> {
@@ -126,54 +341,383 @@ async fn list(path: &Path) -> Result<(), errors::ListError> {
     Ok(())
 }
 
-fn show_pict(pict: pict_decoder::PICT) -> Result<(), String> {
-    use sdl2::{event::Event, keyboard::Keycode};
+async fn info(path: &Path) -> Result<(), errors::InfoError> {
+    let mohawk = Mohawk::open(&path).await?;
+    let header = &mohawk.header;
+
+    let unreferenced = mohawk.unreferenced_size();
+    println!("version            {:#06X}", header.version);
+    println!("compaction         {}", header.compaction);
+    println!("total size         {}", header.total_file_size);
+    println!("resource dir       {:#010X}", header.resource_dir_offset);
+    println!(
+        "file table         {:#06X} (in resource dir), {} bytes",
+        header.file_table_offset_in_resource_dir, header.file_table_size
+    );
+    println!(
+        "unreferenced       {} ({:.2}%)",
+        unreferenced,
+        unreferenced as f64 * 100.0 / header.total_file_size as f64
+    );
+    println!();
+
+    let mut sorted_types: Vec<_> = mohawk.types.iter().collect();
+    sorted_types.sort_unstable_by_key(|(t, _)| *t);
+    println!("type count named     total      min      max");
+    for (type_id, resources) in sorted_types {
+        let sizes = || resources.values().map(|r| r.file.size);
+
+        println!(
+            "{} {:5} {:5} {:9} {:8} {:8}",
+            type_id,
+            resources.len(),
+            resources.values().filter(|r| r.name.is_some()).count(),
+            sizes().map(u64::from).sum::<u64>(),
+            sizes().min().unwrap_or(0),
+            sizes().max().unwrap_or(0),
+        );
+    }
 
-    let sdl_context = sdl2::init()?;
-    let window = sdl_context
-        .video()?
-        .window("rust-sdl2 demo", 800, 600)
-        .build()
-        .unwrap();
+    Ok(())
+}
 
-    let mut canvas = window.into_canvas().build().unwrap();
+async fn browser_entry(
+    type_id: &TypeID,
+    resource_id: &ResourceID,
+    resource: &Resource,
+) -> browser::Entry {
+    browser::Entry {
+        type_id: type_id.clone(),
+        id: *resource_id,
+        name: resource.name.clone(),
+        data: resource.data().await.map_err(|e| e.to_string()),
+    }
+}
 
-    let texture_creator = canvas.texture_creator();
-    let texture = match pict {
-        pict_decoder::PICT::JPEG(raw) => texture_creator.load_texture_bytes(&raw).unwrap(),
-        pict_decoder::PICT::RGB24 {
-            width,
-            height,
-            data,
-        } => {
-            let mut ret = texture_creator
-                .create_texture_streaming(PixelFormatEnum::RGB24, width as u32, height as u32)
-                .unwrap();
-            ret.with_lock(None, |buf, _| buf.copy_from_slice(&data))
-                .unwrap();
-            ret
+async fn browse(path: &Path) -> Result<(), errors::BrowseError> {
+    use errors::BrowseError::*;
+
+    let mohawk = crate::Mohawk::open(path).await?;
+
+    let mut entries = Vec::new();
+    for type_id in [TypeID::PICT, TypeID::MSND] {
+        let Some(resources) = mohawk.types.get(&type_id) else {
+            continue;
+        };
+
+        let mut sorted_resources: Vec<_> = resources.iter().collect();
+        sorted_resources.sort_unstable_by_key(|(id, _)| *id);
+        for (resource_id, resource) in sorted_resources {
+            entries.push(browser_entry(&type_id, resource_id, resource).await);
         }
+    }
+
+    let title = path.display().to_string();
+    spawn_blocking(move || browser::run(&title, entries, 0))
+        .await
+        .map_err(SetupBrowser)?
+        .map_err(Browser)
+}
+
+fn run_player(mut player: Player<impl Backend>, start: Duration) -> Result<(), String> {
+    let (controls, received) = mpsc::channel();
+    thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(result::Result::ok) {
+            if controls.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let print_position = |player: &Player<_>| {
+        println!(
+            "{:.1}s / {:.1}s",
+            player.position().as_secs_f32(),
+            player.duration().as_secs_f32()
+        )
     };
 
-    canvas.clear();
-    let mut event_pump = sdl_context.event_pump().unwrap();
-    'running: loop {
-        canvas.copy(&texture, None, None).unwrap();
-        canvas.present();
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running Ok(()),
-                _ => {}
+    player.seek(start)?;
+    player.play()?;
+    print_position(&player);
+
+    while player.is_playing() {
+        match received.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => match Control::parse(&line) {
+                Some(control) => {
+                    player.apply(control)?;
+                    print_position(&player);
+                }
+                None => eprintln!("unknown control: {}", line.trim()),
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            // stdin closed, let it play until the end
+            Err(mpsc::RecvTimeoutError::Disconnected) => thread::sleep(Duration::from_millis(100)),
+        }
+    }
+
+    Ok(())
+}
+
+async fn play(
+    path: &Path,
+    resource_id: &ResourceID,
+    start: f64,
+    dry_run: bool,
+) -> Result<(), errors::PlayError> {
+    use errors::PlayError::*;
+
+    let mohawk = crate::Mohawk::open(path).await?;
+    let sound = mohawk
+        .get_msnd(resource_id)
+        .await
+        .ok_or(ResourceNotFound)??;
+    let start = Duration::try_from_secs_f64(start).map_err(InvalidStart)?;
+
+    spawn_blocking(move || {
+        if dry_run {
+            run_player(Player::new(player::Null::default(), sound), start)
+        } else {
+            run_player(Player::new(player::Mixer::open()?, sound), start)
+        }
+    })
+    .await
+    .map_err(SetupPlayer)?
+    .map_err(Playback)
+}
+
+async fn grep(paths: &[PathBuf], patterns: &[Pattern]) -> Result<(), errors::GrepError> {
+    use errors::GrepError::*;
+
+    if patterns.is_empty() {
+        return Err(NoPattern);
+    }
+
+    let mut failures = 0;
+    for path in paths {
+        for archive in lyst::install::find_archives(path)
+            .await
+            .map_err(FindArchives)?
+        {
+            let searched = match crate::Mohawk::open(&archive).await {
+                Ok(mohawk) => search::search(&mohawk, patterns).await,
+                Err(e) => Err(e),
+            };
+            let found = match searched {
+                Ok(found) => found,
+                Err(e) => {
+                    // as grep tools, keep searching the other archives
+                    eprintln!("{}: {}", archive.display(), e);
+                    failures += 1;
+                    continue;
+                }
+            };
+
+            for found in found {
+                println!(
+                    "{}:{}:{}:{:#X}: {}",
+                    archive.display(),
+                    found.type_id,
+                    found.id,
+                    found.offset,
+                    found.found
+                );
+            }
+        }
+    }
+
+    match failures {
+        0 => Ok(()),
+        count => Err(Failures(count)),
+    }
+}
+
+/// Compare a single picture to its reference, describing the failure if any
+fn compare_picture(raw: &[u8], reference: &Path, diff: &Path) -> result::Result<(), String> {
+    let decoded =
+        lyst::convert::decode_picture(raw).map_err(|e| format!("unable to decode: {}", e))?;
+    let reference = image::open(reference)
+        .map_err(|e| format!("unable to read reference: {}", e))?
+        .into_rgb8();
+
+    let (description, diff_image) = match lyst::compare::compare(&decoded, &reference) {
+        Some(comparison) if comparison.mismatched == 0 => return Ok(()),
+        Some(comparison) => (
+            format!(
+                "{}/{} pixels differ, PSNR {:.2} dB",
+                comparison.mismatched, comparison.total, comparison.psnr
+            ),
+            comparison.diff,
+        ),
+        None => (
+            format!(
+                "size {}x{}, reference {}x{}",
+                decoded.width(),
+                decoded.height(),
+                reference.width(),
+                reference.height()
+            ),
+            lyst::compare::side_by_side(&decoded, &reference),
+        ),
+    };
+
+    match diff_image.save(diff) {
+        Ok(()) => Err(format!(
+            "{}, diff written to {}",
+            description,
+            diff.display()
+        )),
+        Err(e) => Err(format!("{}, unable to write diff: {}", description, e)),
+    }
+}
+
+async fn compare(
+    path: &Path,
+    reference_dir: &Path,
+    diff_dir: &Path,
+) -> Result<(), errors::CompareError> {
+    use errors::CompareError::*;
+
+    let mohawk = crate::Mohawk::open(path).await?;
+    let resources = mohawk.types.get(&TypeID::PICT).ok_or(NoPicture)?;
+
+    let mut sorted_resources: Vec<_> = resources.iter().collect();
+    sorted_resources.sort_unstable_by_key(|(id, _)| *id);
+
+    let mut raws = Vec::new();
+    let mut missing = 0;
+    for (id, resource) in sorted_resources {
+        let reference = reference_dir.join(format!("{}.png", id));
+        if !reference.is_file() {
+            missing += 1;
+            continue;
+        }
+        raws.push((*id, reference, resource.data().await?));
+    }
+
+    fs::create_dir_all(diff_dir).map_err(CreateDiffDir)?;
+    let diff_dir = diff_dir.to_path_buf();
+    let (ok, failed) = spawn_blocking(move || {
+        let (mut ok, mut failed) = (0, 0);
+        for (id, reference, raw) in raws {
+            let diff = diff_dir.join(format!("{}.png", id));
+            match compare_picture(&raw, &reference, &diff) {
+                Ok(()) => {
+                    ok += 1;
+                    println!("{:>6} ok", id);
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("{:>6} {}", id, e);
+                }
+            }
+        }
+
+        (ok, failed)
+    })
+    .await
+    .map_err(SetupCompare)?;
+
+    println!(
+        "{} identical, {} failed, {} without reference",
+        ok, failed, missing
+    );
+
+    match failed {
+        0 => Ok(()),
+        count => Err(Failures(count)),
+    }
+}
+
+async fn serve(path: &Path, port: u16) -> Result<(), errors::ServeError> {
+    use errors::ServeError::*;
+
+    let archives = lyst::install::find_archives(path)
+        .await
+        .map_err(FindArchives)?;
+    let router = lyst::server::router(lyst::server::open_archives(archives).await);
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .await
+        .map_err(Bind)?;
+    println!(
+        "listening on http://{}",
+        listener.local_addr().map_err(Bind)?
+    );
+
+    axum::serve(listener, router).await.map_err(Server)
+}
+
+async fn contact_sheet(
+    path: &Path,
+    out: &Path,
+    columns: u32,
+) -> Result<(), errors::ContactSheetError> {
+    use errors::ContactSheetError::*;
+
+    let mohawk = crate::Mohawk::open(path).await?;
+    let resources = mohawk.types.get(&TypeID::PICT).ok_or(NoPicture)?;
+
+    let mut sorted_resources: Vec<_> = resources.iter().collect();
+    sorted_resources.sort_unstable_by_key(|(id, _)| *id);
+
+    let mut raws = Vec::with_capacity(sorted_resources.len());
+    for (id, resource) in sorted_resources {
+        raws.push((*id, resource.name.clone(), resource.data().await?));
+    }
+
+    let out = out.to_path_buf();
+    spawn_blocking(move || {
+        let cells: Vec<_> = raws
+            .into_iter()
+            .map(|(id, name, raw)| {
+                let image = lyst::convert::decode_picture(&raw).map_err(|e| e.to_string());
+
+                lyst::contact_sheet::Cell { id, name, image }
+            })
+            .collect();
+
+        lyst::contact_sheet::render(&cells, columns).save(out)
+    })
+    .await
+    .map_err(SetupRender)??;
+
+    Ok(())
+}
+
+async fn verify_install(path: &Path, json: bool) -> Result<(), errors::VerifyInstallError> {
+    use errors::VerifyInstallError::*;
+
+    let archives = lyst::install::find_archives(path)
+        .await
+        .map_err(FindArchives)?;
+    let report = lyst::verify::verify(archives).await;
+
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &report)?;
+        println!();
+    } else {
+        for archive in &report.archives {
+            println!("{}", archive.path.display());
+            if let Some(error) = &archive.error {
+                println!("  unable to open: {}", error);
+            }
+
+            for (type_id, types) in &archive.types {
+                println!(
+                    "  {} {:>6} ok {:>6} failed",
+                    type_id, types.success, types.failure
+                );
+                for failure in &types.failures {
+                    println!("    {:>6}: {}", failure.id, failure.error);
+                }
             }
         }
-        // The rest of the game loop goes here...
+    }
 
-        canvas.present();
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    match report.failure_count() {
+        0 => Ok(()),
+        count => Err(Failures(count)),
     }
 }
 
@@ -184,7 +728,7 @@ async fn extract(
 ) -> Result<(), errors::ExtractError> {
     use errors::ExtractError::*;
 
-    let mohawk = crate::Mohawk::open(path).await?;
+    let mohawk = crate::Mohawk::open(&path).await?;
 
     match type_id {
         TypeID::MSND => {
@@ -201,12 +745,16 @@ async fn extract(
                 .map_err(WriteExtracted)?;
         }
         TypeID::PICT => {
-            let pict = mohawk
-                .get_pict(resource_id)
-                .await
-                .ok_or(ResourceNotFound)??;
+            let resource = mohawk
+                .types
+                .get(type_id)
+                .ok_or(TypeNotFound)?
+                .get(resource_id)
+                .ok_or(ResourceNotFound)?;
+            let entry = browser_entry(type_id, resource_id, resource).await;
 
-            spawn_blocking(|| show_pict(pict))
+            let title = path.as_ref().display().to_string();
+            spawn_blocking(move || browser::run(&title, vec![entry], 0))
                 .await
                 .map_err(SetupPictShow)?
                 .map_err(ShowPict)?;
@@ -217,12 +765,80 @@ async fn extract(
     Ok(())
 }
 
-#[cfg(not(feature = "dep:console-subscriber"))]
+async fn pict_dump(path: &Path, resource_id: &ResourceID) -> Result<(), errors::PictDumpError> {
+    use errors::PictDumpError::*;
+
+    let mohawk = crate::Mohawk::open(&path).await?;
+    let raw = mohawk
+        .types
+        .get(&TypeID::PICT)
+        .and_then(|resources| resources.get(resource_id))
+        .ok_or(ResourceNotFound)?
+        .data()
+        .await?;
+
+    let dump = pict_decoder::dump(raw.as_slice());
+    for entry in &dump.entries {
+        match entry.opcode {
+            Some(opcode) => println!("{:06X} {:04X} {}", entry.offset, opcode, entry.name),
+            None => println!("{:06X}      {}", entry.offset, entry.name),
+        }
+        for (name, value) in &entry.fields {
+            println!("                {}: {}", name, value);
+        }
+    }
+
+    match &dump.error {
+        Some((offset, e)) => println!("stopped at {:06X}: {}", offset, e),
+        None => println!("end of picture, {} bytes remaining", dump.remaining),
+    }
+
+    match pict_decoder::PICT::parse(raw.as_slice()) {
+        Ok(_) => println!("decoding: ok"),
+        Err(e) => println!("decoding: {}", e),
+    }
+
+    Ok(())
+}
+
+async fn extract_all(
+    path: &Path,
+    output: &Path,
+    jobs: Option<usize>,
+) -> Result<(), errors::ExtractAllError> {
+    use errors::ExtractAllError::*;
+
+    let jobs = jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1);
+    let archives = lyst::install::find_archives(path)
+        .await
+        .map_err(FindArchives)?;
+
+    let summary = lyst::extract::extract_all(archives, output, jobs).await;
+
+    println!(
+        "{} resources extracted, {} failed",
+        summary.extracted,
+        summary.failures.len()
+    );
+    if summary.failures.is_empty() {
+        return Ok(());
+    }
+
+    for (location, e) in &summary.failures {
+        eprintln!("{}: {}", location, e);
+    }
+
+    Err(Failures(summary.failures.len()))
+}
+
+#[cfg(not(feature = "console"))]
 fn setup_tracing() {
     tracing_subscriber::fmt::init();
 }
 
-#[cfg(feature = "dep:console-subscriber")]
+#[cfg(feature = "console")]
 fn setup_tracing() {
     console_subscriber::init();
 }
@@ -235,6 +851,7 @@ async fn main() -> ExitCode {
 
     let ret: Result<(), errors::Error> = match &cli.command {
         Commands::List { path } => list(path).await.map_err(errors::Error::List),
+        Commands::Info { path } => info(path).await.map_err(errors::Error::Info),
         Commands::Extract {
             path,
             type_id,
@@ -242,6 +859,58 @@ async fn main() -> ExitCode {
         } => extract(path, type_id, resource_id)
             .await
             .map_err(errors::Error::Extract),
+        Commands::Browse { path } => browse(path).await.map_err(errors::Error::Browse),
+        Commands::Play {
+            path,
+            resource_id,
+            start,
+            dry_run,
+        } => play(path, resource_id, *start, *dry_run)
+            .await
+            .map_err(errors::Error::Play),
+        Commands::PictDump { path, resource_id } => pict_dump(path, resource_id)
+            .await
+            .map_err(errors::Error::PictDump),
+        Commands::Grep {
+            paths,
+            name,
+            name_regex,
+            bytes,
+            text,
+        } => {
+            let patterns = [
+                name.as_deref().map(Pattern::name),
+                name_regex.as_deref().map(Pattern::name_regex),
+                bytes.as_deref().map(Pattern::bytes),
+                text.as_deref().map(Pattern::text),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Result<Vec<_>, _>>();
+
+            match patterns {
+                Ok(patterns) => grep(paths, &patterns).await,
+                Err(e) => Err(e.into()),
+            }
+            .map_err(errors::Error::Grep)
+        }
+        Commands::Serve { path, port } => serve(path, *port).await.map_err(errors::Error::Serve),
+        Commands::Compare {
+            path,
+            reference_dir,
+            diff_dir,
+        } => compare(path, reference_dir, diff_dir)
+            .await
+            .map_err(errors::Error::Compare),
+        Commands::ContactSheet { path, out, columns } => contact_sheet(path, out, *columns)
+            .await
+            .map_err(errors::Error::ContactSheet),
+        Commands::VerifyInstall { path, json } => verify_install(path, *json)
+            .await
+            .map_err(errors::Error::VerifyInstall),
+        Commands::ExtractAll { path, output, jobs } => extract_all(path, output, *jobs)
+            .await
+            .map_err(errors::Error::ExtractAll),
     };
 
     if let Err(e) = ret {
@@ -1,11 +1,12 @@
 use core::fmt;
-use std::{collections::HashMap, fmt::Write, io::SeekFrom, path::Path, string};
+use std::{cmp, collections::HashMap, fmt::Write, io::SeekFrom, path::Path, string};
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
 use tracing::{trace, trace_span, warn};
 
 use async_stream::try_stream;
 use tokio_stream::StreamExt;
 
+pub mod msnd;
 mod pict;
 mod reader;
 use reader::Reader;
@@ -36,6 +37,8 @@ pub enum Error {
 
     #[error("parse pict: {0}")]
     PICT(#[from] pict_decoder::Error),
+    #[error("parse msnd: {0}")]
+    MSND(#[from] msnd::Error),
 }
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -46,7 +49,7 @@ pub struct Resource {
 }
 
 pub struct File {
-    offset: u64,
+    pub offset: u64,
     pub size: u32,
     pub flag: u8,
     pub unknown: u16,
@@ -62,6 +65,14 @@ impl Resource {
     pub fn reader(&self) -> Reader {
         Reader::take(&self.reader, self.file.size as usize)
     }
+
+    /// Read the whole content of the resource
+    pub async fn data(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.file.size as usize);
+        self.reader().read_to_end(&mut buf).await?;
+
+        Ok(buf)
+    }
 }
 
 #[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
@@ -103,6 +114,7 @@ type FileID = u16;
 // http://insidethelink.ortiche.net/wiki/index.php/Mohawk_archive_format
 pub struct Mohawk {
     //pub msnd: Option<HashMap<ResourceID, Resource>>,
+    pub header: RSRCHeader,
     pub types: HashMap<TypeID, HashMap<ResourceID, Resource>>,
 }
 
@@ -114,11 +126,13 @@ impl Mohawk {
 
         let total_file_size = parse_iff_header(&mut reader).await?;
         trace!(total_file_size, "iff parsed");
+        let header = parse_rsrc_header(&mut reader, total_file_size).await?;
         let RSRCHeader {
             resource_dir_offset,
             file_table_offset_in_resource_dir,
             file_table_size,
-        } = parse_rsrc_header(&mut reader, total_file_size).await?;
+            ..
+        } = header;
         trace!(
             resource_dir_offset,
             file_table_offset_in_resource_dir,
@@ -253,7 +267,47 @@ impl Mohawk {
         .into_iter()
         .collect::<HashMap<TypeID, _>>();
 
-        Ok(Self { types })
+        Ok(Self { header, types })
+    }
+
+    /// Count bytes of the file which are neither part of the headers, the resource directory
+    /// nor a resource
+    pub fn unreferenced_size(&self) -> u64 {
+        const HEADERS_SIZE: u64 = 8 + 20;
+
+        let resource_dir_end = self.header.resource_dir_offset as u64
+            + self.header.file_table_offset_in_resource_dir as u64
+            + self.header.file_table_size as u64;
+
+        let mut ranges = self
+            .types
+            .values()
+            .flat_map(|resources| resources.values())
+            .map(|resource| {
+                (
+                    resource.file.offset,
+                    resource.file.offset + resource.file.size as u64,
+                )
+            })
+            .chain([
+                (0, HEADERS_SIZE),
+                (self.header.resource_dir_offset as u64, resource_dir_end),
+            ])
+            .collect::<Vec<_>>();
+        ranges.sort_unstable();
+
+        // resources might share the same file
+        let mut referenced = 0;
+        let mut covered_until = 0;
+        for (start, end) in ranges {
+            let start = cmp::max(start, covered_until);
+            if end > start {
+                referenced += end - start;
+                covered_until = end;
+            }
+        }
+
+        (self.header.total_file_size as u64).saturating_sub(referenced)
     }
 
     pub async fn get_pict(&self, id: &ResourceID) -> Option<Result<pict_decoder::PICT>> {
@@ -261,13 +315,23 @@ impl Mohawk {
 
         let res = self.types.get(&TypeID::PICT).and_then(|m| m.get(id))?;
 
-        let mut buf = Vec::new();
-        let read = res.reader().read_to_end(&mut buf).await;
-        if let Err(e) = read {
-            return Some(Err(Error::Reader(e)));
-        }
+        Some(
+            res.data()
+                .await
+                .and_then(|buf| pict_decoder::PICT::parse(buf.as_slice()).map_err(Error::PICT)),
+        )
+    }
+
+    pub async fn get_msnd(&self, id: &ResourceID) -> Option<Result<msnd::Sound>> {
+        trace!("get msnd");
 
-        Some(pict_decoder::PICT::parse(buf.as_slice()).map_err(Error::PICT))
+        let res = self.types.get(&TypeID::MSND).and_then(|m| m.get(id))?;
+
+        Some(
+            res.data()
+                .await
+                .and_then(|buf| msnd::Sound::parse(buf.as_slice()).map_err(Error::MSND)),
+        )
     }
 }
 
@@ -284,7 +348,10 @@ async fn parse_iff_header(reader: &mut Reader) -> Result<u32> {
         .map_err(Error::Reader)
 }
 
-struct RSRCHeader {
+pub struct RSRCHeader {
+    pub version: u16,
+    pub compaction: u16,
+    pub total_file_size: u32,
     pub resource_dir_offset: u32,
     pub file_table_offset_in_resource_dir: u16,
     pub file_table_size: u16,
@@ -308,6 +375,9 @@ async fn parse_rsrc_header(reader: &mut Reader, total_file_size: u32) -> Result<
     }
 
     Ok(RSRCHeader {
+        version,
+        compaction,
+        total_file_size,
         resource_dir_offset: reader.read_u32().await?,
         file_table_offset_in_resource_dir: reader.read_u16().await?,
         file_table_size: reader.read_u16().await?,
@@ -404,10 +474,80 @@ async fn parse_file_table(
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{fs, path::PathBuf};
 
     use super::*;
-    use crate::tests::get_known_files;
+    use crate::tests::{build_mohawk, get_known_files, BuiltResource};
+
+    #[test_log::test(tokio::test)]
+    async fn open_built_archive() {
+        let dir = tempfile::tempdir().expect("to create temporary directory");
+        let path = dir.path().join("TEST.DAT");
+        fs::write(
+            &path,
+            build_mohawk(&[
+                BuiltResource {
+                    type_id: *b"PICT",
+                    id: 3,
+                    name: Some("first"),
+                    data: b"abc",
+                },
+                BuiltResource {
+                    type_id: *b"PICT",
+                    id: 1,
+                    name: None,
+                    data: b"defgh",
+                },
+                BuiltResource {
+                    type_id: *b"tBMP",
+                    id: 3,
+                    name: Some("other"),
+                    data: b"",
+                },
+            ]),
+        )
+        .expect("to write archive");
+
+        let mohawk = Mohawk::open(&path).await.expect("to parse Mohawk file");
+
+        let picts = &mohawk.types[&TypeID::PICT];
+        assert_eq!(picts.len(), 2);
+        assert_eq!(picts[&3].name.as_deref(), Some("first"));
+        assert_eq!(picts[&3].data().await.unwrap(), b"abc");
+        assert_eq!(picts[&1].name, None);
+        assert_eq!(picts[&1].data().await.unwrap(), b"defgh");
+
+        let others = &mohawk.types[&TypeID::from(*b"tBMP")];
+        assert_eq!(others[&3].name.as_deref(), Some("other"));
+        assert!(others[&3].data().await.unwrap().is_empty());
+
+        assert_eq!(mohawk.header.version, 0x100);
+        assert_eq!(
+            mohawk.header.total_file_size as u64,
+            fs::metadata(&path).unwrap().len()
+        );
+        assert_eq!(mohawk.unreferenced_size(), 0);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn count_unreferenced_bytes() {
+        let mut raw = build_mohawk(&[BuiltResource {
+            type_id: *b"PICT",
+            id: 1,
+            name: None,
+            data: b"abcdef",
+        }]);
+        // shrink the only file from 6 to 2 bytes
+        let file_table_start = raw.len() - (4 + 10);
+        raw[file_table_start + 4 + 4..][..2].copy_from_slice(&2u16.to_be_bytes());
+
+        let dir = tempfile::tempdir().expect("to create temporary directory");
+        let path = dir.path().join("TEST.DAT");
+        fs::write(&path, raw).expect("to write archive");
+
+        let mohawk = Mohawk::open(&path).await.expect("to parse Mohawk file");
+        assert_eq!(mohawk.unreferenced_size(), 4);
+    }
 
     #[test_log::test(tokio::test)]
     #[ignore]
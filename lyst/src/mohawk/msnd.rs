@@ -0,0 +1,362 @@
+use bytes::Buf;
+use strum::FromRepr;
+use tracing::trace;
+
+// http://insidethelink.ortiche.net/wiki/index.php/Mohawk_sound_format
+// wrapped in its own Mohawk-like header, followed by chunks until the data
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("unexpected end of buffer")]
+    UnexpectedEOB,
+
+    #[error("unexpected MHWK signature")]
+    MHWKSignature,
+    #[error("unexpected WAVE signature")]
+    WAVESignature,
+    #[error("unknown chunk: {0:?}")]
+    UnknownChunk([u8; 4]),
+    #[error("no data chunk found")]
+    NoData,
+    #[error("unsupported encoding: {0}")]
+    UnsupportedEncoding(u16),
+    #[error("unsupported bits per sample: {0}")]
+    UnsupportedBitsPerSample(u8),
+    #[error("unsupported channels count: {0}")]
+    UnsupportedChannels(u8),
+    #[error("sample rate is zero")]
+    NoSampleRate,
+}
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(FromRepr)]
+#[repr(u16)]
+#[allow(clippy::upper_case_acronyms)]
+enum Encoding {
+    Raw = 0,
+    ADPCM = 1,
+    MPEG2 = 2,
+}
+
+/// Decoded sound, as signed 16 bits interleaved samples
+pub struct Sound {
+    pub sample_rate: u16,
+    pub channels: u8,
+    pub loop_count: u16,
+    pub samples: Vec<i16>,
+}
+
+fn ensure_remains_bytes(buf: &impl Buf, amount: usize) -> Result<()> {
+    if buf.remaining() < amount {
+        return Err(Error::UnexpectedEOB);
+    }
+
+    Ok(())
+}
+
+fn read_4_bytes(buf: &mut impl Buf) -> Result<[u8; 4]> {
+    ensure_remains_bytes(buf, 4)?;
+
+    let mut buffer = [0u8; 4];
+    buf.copy_to_slice(&mut buffer);
+
+    Ok(buffer)
+}
+
+impl Sound {
+    pub fn parse(mut buf: impl Buf) -> Result<Self> {
+        use Error::*;
+
+        if read_4_bytes(&mut buf)? != *b"MHWK" {
+            return Err(MHWKSignature);
+        }
+        ensure_remains_bytes(&buf, 4)?;
+        let _size = buf.get_u32();
+        if read_4_bytes(&mut buf)? != *b"WAVE" {
+            return Err(WAVESignature);
+        }
+
+        while buf.has_remaining() {
+            let tag = read_4_bytes(&mut buf)?;
+            trace!("got chunk {:?}", tag);
+
+            match &tag {
+                // seek table for ADPCM, not needed when decoding linearly
+                b"ADPC" => {
+                    ensure_remains_bytes(&buf, 8)?;
+                    let _size = buf.get_u32();
+                    let items_count = buf.get_u16() as usize;
+                    let channels = buf.get_u16() as usize;
+
+                    let items_size = items_count * (4 + channels * 4);
+                    ensure_remains_bytes(&buf, items_size)?;
+                    buf.advance(items_size);
+                }
+                // animation synchronisation, unused
+                b"Cue#" => {
+                    ensure_remains_bytes(&buf, 4)?;
+                    let size = buf.get_u32() as usize;
+                    ensure_remains_bytes(&buf, size)?;
+                    buf.advance(size);
+                }
+                b"Data" => return Self::parse_data(buf),
+                _ => return Err(UnknownChunk(tag)),
+            }
+        }
+
+        Err(NoData)
+    }
+
+    fn parse_data(mut buf: impl Buf) -> Result<Self> {
+        use Error::*;
+
+        const HEADER_SIZE: usize = 20;
+
+        ensure_remains_bytes(&buf, 4 + HEADER_SIZE)?;
+        let data_size = (buf.get_u32() as usize)
+            .checked_sub(HEADER_SIZE)
+            .ok_or(UnexpectedEOB)?;
+        let sample_rate = buf.get_u16();
+        let sample_count = buf.get_u32() as usize;
+        let bits_per_sample = buf.get_u8();
+        let channels = buf.get_u8();
+        let raw_encoding = buf.get_u16();
+        let loop_count = buf.get_u16();
+        let _loop_start = buf.get_u32();
+        let _loop_end = buf.get_u32();
+
+        if !(1..=2).contains(&channels) {
+            return Err(UnsupportedChannels(channels));
+        }
+        if sample_rate == 0 {
+            return Err(NoSampleRate);
+        }
+
+        ensure_remains_bytes(&buf, data_size)?;
+        let data = buf.take(data_size);
+
+        let mut samples = match Encoding::from_repr(raw_encoding) {
+            Some(Encoding::Raw) if bits_per_sample == 8 => {
+                let mut data = data;
+                let mut ret = Vec::with_capacity(data.remaining());
+                while data.has_remaining() {
+                    ret.push(((data.get_u8() as i16) - 0x80) << 8);
+                }
+                ret
+            }
+            Some(Encoding::Raw) => return Err(UnsupportedBitsPerSample(bits_per_sample)),
+            Some(Encoding::ADPCM) => decode_ima_adpcm(data, channels as usize),
+            Some(Encoding::MPEG2) | None => return Err(UnsupportedEncoding(raw_encoding)),
+        };
+        samples.truncate(sample_count * channels as usize);
+
+        Ok(Self {
+            sample_rate,
+            channels,
+            loop_count,
+            samples,
+        })
+    }
+
+    pub fn duration(&self) -> std::time::Duration {
+        let frames = self.samples.len() / self.channels as usize;
+
+        std::time::Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Copy of the sound starting at the given position
+    pub fn skip(&self, from: std::time::Duration) -> Self {
+        let frame = (from.as_secs_f64() * self.sample_rate as f64) as usize;
+        let start = std::cmp::min(frame * self.channels as usize, self.samples.len());
+
+        Self {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            loop_count: self.loop_count,
+            samples: self.samples[start..].to_vec(),
+        }
+    }
+
+    /// Encode as a 16 bits PCM WAVE file
+    pub fn to_wav(&self) -> Vec<u8> {
+        const HEADER_SIZE: usize = 44;
+
+        let data_size = self.samples.len() * 2;
+        let block_align = self.channels as u16 * 2;
+
+        let mut ret = Vec::with_capacity(HEADER_SIZE + data_size);
+        ret.extend_from_slice(b"RIFF");
+        ret.extend_from_slice(&((HEADER_SIZE - 8 + data_size) as u32).to_le_bytes());
+        ret.extend_from_slice(b"WAVE");
+
+        ret.extend_from_slice(b"fmt ");
+        ret.extend_from_slice(&16u32.to_le_bytes());
+        ret.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        ret.extend_from_slice(&(self.channels as u16).to_le_bytes());
+        ret.extend_from_slice(&(self.sample_rate as u32).to_le_bytes());
+        ret.extend_from_slice(&(self.sample_rate as u32 * block_align as u32).to_le_bytes());
+        ret.extend_from_slice(&block_align.to_le_bytes());
+        ret.extend_from_slice(&16u16.to_le_bytes());
+
+        ret.extend_from_slice(b"data");
+        ret.extend_from_slice(&(data_size as u32).to_le_bytes());
+        for sample in &self.samples {
+            ret.extend_from_slice(&sample.to_le_bytes());
+        }
+
+        ret
+    }
+}
+
+// http://www.cs.columbia.edu/~hgs/audio/dvi/IMA_ADPCM.pdf
+const IMA_INDEX_TABLE: [i8; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+const IMA_STEP_TABLE: [i16; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+#[derive(Default)]
+struct IMAState {
+    last: i16,
+    step_index: usize,
+}
+
+impl IMAState {
+    fn decode(&mut self, nibble: u8) -> i16 {
+        let step = IMA_STEP_TABLE[self.step_index] as i32;
+
+        let mut diff = step >> 3;
+        if nibble & 0b0001 != 0 {
+            diff += step >> 2;
+        }
+        if nibble & 0b0010 != 0 {
+            diff += step >> 1;
+        }
+        if nibble & 0b0100 != 0 {
+            diff += step;
+        }
+        if nibble & 0b1000 != 0 {
+            diff = -diff;
+        }
+
+        self.last = (self.last as i32 + diff).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.step_index = (self.step_index as isize + IMA_INDEX_TABLE[nibble as usize] as isize)
+            .clamp(0, IMA_STEP_TABLE.len() as isize - 1) as usize;
+
+        self.last
+    }
+}
+
+/// decode IMA ADPCM, high nibble first, alternating channels on stereo
+fn decode_ima_adpcm(mut data: impl Buf, channels: usize) -> Vec<i16> {
+    let mut states = [IMAState::default(), IMAState::default()];
+
+    let mut ret = Vec::with_capacity(data.remaining() * 2);
+    while data.has_remaining() {
+        let byte = data.get_u8();
+        ret.push(states[0].decode(byte >> 4));
+        ret.push(states[channels - 1].decode(byte & 0x0F));
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn build(encoding: u16, bits_per_sample: u8, channels: u8, data: &[u8]) -> Vec<u8> {
+        let sample_count = match encoding {
+            0 => data.len() / channels as usize,
+            _ => data.len() * 2 / channels as usize,
+        };
+
+        let mut ret = Vec::new();
+        ret.extend_from_slice(b"MHWK");
+        ret.extend_from_slice(&(4 + 8 + 20 + data.len() as u32).to_be_bytes());
+        ret.extend_from_slice(b"WAVE");
+        ret.extend_from_slice(b"Cue#");
+        ret.extend_from_slice(&2u32.to_be_bytes());
+        ret.extend_from_slice(&[0, 0]);
+        ret.extend_from_slice(b"Data");
+        ret.extend_from_slice(&(20 + data.len() as u32).to_be_bytes());
+        ret.extend_from_slice(&22050u16.to_be_bytes());
+        ret.extend_from_slice(&(sample_count as u32).to_be_bytes());
+        ret.push(bits_per_sample);
+        ret.push(channels);
+        ret.extend_from_slice(&encoding.to_be_bytes());
+        ret.extend_from_slice(&0u16.to_be_bytes());
+        ret.extend_from_slice(&0u32.to_be_bytes());
+        ret.extend_from_slice(&0u32.to_be_bytes());
+        ret.extend_from_slice(data);
+
+        ret
+    }
+
+    #[test]
+    fn parse_raw() {
+        let sound = Sound::parse(build(0, 8, 1, &[0x80, 0xFF, 0x00]).as_slice()).unwrap();
+
+        assert_eq!(sound.sample_rate, 22050);
+        assert_eq!(sound.samples, [0, 0x7F00, -0x8000]);
+    }
+
+    #[test]
+    fn parse_adpcm() {
+        let sound = Sound::parse(build(1, 4, 1, &[0x70, 0x08]).as_slice()).unwrap();
+
+        assert_eq!(sound.samples, [11, 13, 14, 13]);
+    }
+
+    #[test]
+    fn unsupported_encoding() {
+        assert!(matches!(
+            Sound::parse(build(2, 16, 1, &[]).as_slice()),
+            Err(Error::UnsupportedEncoding(2))
+        ));
+    }
+
+    #[test]
+    fn zero_sample_rate() {
+        let mut raw = build(0, 8, 1, &[0x80]);
+        raw[30..32].copy_from_slice(&0u16.to_be_bytes());
+
+        assert!(matches!(
+            Sound::parse(raw.as_slice()),
+            Err(Error::NoSampleRate)
+        ));
+    }
+
+    #[test]
+    fn skip_frames() {
+        let sound = Sound {
+            sample_rate: 2,
+            channels: 2,
+            loop_count: 0,
+            samples: vec![1, 2, 3, 4, 5, 6],
+        };
+
+        assert_eq!(sound.skip(Duration::from_millis(500)).samples, [3, 4, 5, 6]);
+        assert!(sound.skip(Duration::from_secs(10)).samples.is_empty());
+    }
+
+    #[test]
+    fn wav_header() {
+        let sound = Sound {
+            sample_rate: 11025,
+            channels: 2,
+            loop_count: 0,
+            samples: vec![1, -1],
+        };
+        let wav = sound.to_wav();
+
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + 4);
+        assert_eq!(&wav[40..44], &4u32.to_le_bytes());
+        assert_eq!(&wav[44..], &[1, 0, 0xFF, 0xFF]);
+    }
+}
@@ -3,7 +3,7 @@ use std::{
     future::Future,
     io::SeekFrom,
     path::Path,
-    pin::{pin, Pin},
+    pin::Pin,
     task::{ready, Context, Poll},
 };
 use tracing::{trace, trace_span, warn, Instrument};
@@ -42,7 +42,7 @@ impl fmt::Display for Commands {
     }
 }
 
-type BoxedFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+type BoxedFuture<T> = Pin<Box<dyn Future<Output = T> + Send + Sync>>;
 
 #[pin_project::pin_project]
 pub struct Reader {
@@ -107,8 +107,8 @@ impl Reader {
         rx.await.unwrap()
     }
 
-    fn poll_fill_buf_inner<'a>(
-        self: &mut Pin<&'a mut Self>,
+    fn poll_fill_buf_inner(
+        self: &mut Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<io::Result<()>> {
         if !self.as_mut().project().buffer.is_empty() {
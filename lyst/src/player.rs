@@ -0,0 +1,303 @@
+use std::{
+    cmp,
+    time::{Duration, Instant},
+};
+
+use sdl2::{
+    mixer::{self, Channel, Chunk, LoaderRWops},
+    rwops::RWops,
+};
+
+use crate::mohawk::msnd::Sound;
+
+/// Where decoded sounds are sent to
+pub trait Backend {
+    /// Start playing the sound from the given position, replacing what was playing
+    fn play(&mut self, sound: &Sound, from: Duration) -> Result<(), String>;
+    fn stop(&mut self);
+    fn is_playing(&self) -> bool;
+}
+
+/// Backend without any output, only simulating the time taken to play
+#[derive(Default)]
+pub struct Null {
+    until: Option<Instant>,
+}
+
+impl Backend for Null {
+    fn play(&mut self, sound: &Sound, from: Duration) -> Result<(), String> {
+        self.until = Some(Instant::now() + sound.duration().saturating_sub(from));
+
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        self.until = None;
+    }
+
+    fn is_playing(&self) -> bool {
+        self.until.is_some_and(|until| Instant::now() < until)
+    }
+}
+
+/// Backend playing through SDL_mixer
+pub struct Mixer {
+    _sdl: sdl2::Sdl,
+    _audio: sdl2::AudioSubsystem,
+    playing: Option<(Channel, Chunk)>,
+}
+
+impl Mixer {
+    pub fn open() -> Result<Self, String> {
+        let sdl = sdl2::init()?;
+        let audio = sdl.audio()?;
+        mixer::open_audio(44_100, mixer::AUDIO_S16LSB, mixer::DEFAULT_CHANNELS, 1_024)?;
+
+        Ok(Self {
+            _sdl: sdl,
+            _audio: audio,
+            playing: None,
+        })
+    }
+}
+
+impl Backend for Mixer {
+    fn play(&mut self, sound: &Sound, from: Duration) -> Result<(), String> {
+        self.stop();
+
+        let wav = sound.skip(from).to_wav();
+        let chunk = RWops::from_bytes(&wav)?.load_wav()?;
+        let channel = Channel::all().play(&chunk, 0)?;
+        self.playing = Some((channel, chunk));
+
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some((channel, _)) = self.playing.take() {
+            channel.halt();
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        self.playing
+            .as_ref()
+            .is_some_and(|(channel, _)| channel.is_playing())
+    }
+}
+
+impl Drop for Mixer {
+    fn drop(&mut self) {
+        self.stop();
+        mixer::close_audio();
+    }
+}
+
+/// Command changing the playback
+#[derive(Debug, PartialEq)]
+pub enum Control {
+    Seek(Duration),
+    /// Move by the given amount of seconds, backward if negative
+    SeekBy(f64),
+    Stop,
+}
+
+impl Control {
+    /// Parse `N` to seek to N seconds, `+N` or `-N` to move by N seconds, `stop` or `q` to stop
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+
+        match raw {
+            "stop" | "s" | "quit" | "q" => Some(Self::Stop),
+            _ if raw.starts_with(['+', '-']) => raw
+                .parse()
+                .ok()
+                .filter(|seconds: &f64| seconds.is_finite())
+                .map(Self::SeekBy),
+            _ => Duration::try_from_secs_f64(raw.parse().ok()?)
+                .ok()
+                .map(Self::Seek),
+        }
+    }
+}
+
+/// Play a sound, keeping track of the position to allow seeking
+pub struct Player<B: Backend> {
+    backend: B,
+    sound: Sound,
+    /// Position when last started or stopped
+    start: Duration,
+    started_at: Option<Instant>,
+}
+
+impl<B: Backend> Player<B> {
+    pub fn new(backend: B, sound: Sound) -> Self {
+        Self {
+            backend,
+            sound,
+            start: Duration::ZERO,
+            started_at: None,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.sound.duration()
+    }
+
+    pub fn position(&self) -> Duration {
+        match self.started_at {
+            Some(started_at) => cmp::min(self.start + started_at.elapsed(), self.duration()),
+            None => self.start,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.started_at.is_some() && self.backend.is_playing()
+    }
+
+    /// Start playing from the current position
+    pub fn play(&mut self) -> Result<(), String> {
+        self.start = self.position();
+        self.backend.play(&self.sound, self.start)?;
+        self.started_at = Some(Instant::now());
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.start = self.position();
+        self.started_at = None;
+        self.backend.stop();
+    }
+
+    /// Move to the given position, continuing to play if it was
+    pub fn seek(&mut self, to: Duration) -> Result<(), String> {
+        let was_playing = self.started_at.is_some();
+
+        self.stop();
+        self.start = cmp::min(to, self.duration());
+
+        if was_playing {
+            self.play()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn seek_by(&mut self, seconds: f64) -> Result<(), String> {
+        let to = (self.position().as_secs_f64() + seconds).max(0.0);
+
+        self.seek(Duration::try_from_secs_f64(to).map_err(|e| e.to_string())?)
+    }
+
+    pub fn apply(&mut self, control: Control) -> Result<(), String> {
+        match control {
+            Control::Seek(to) => self.seek(to),
+            Control::SeekBy(seconds) => self.seek_by(seconds),
+            Control::Stop => {
+                self.stop();
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        played_from: Vec<Duration>,
+        playing: bool,
+    }
+
+    impl Backend for &mut Recorder {
+        fn play(&mut self, _: &Sound, from: Duration) -> Result<(), String> {
+            self.played_from.push(from);
+            self.playing = true;
+            Ok(())
+        }
+
+        fn stop(&mut self) {
+            self.playing = false;
+        }
+
+        fn is_playing(&self) -> bool {
+            self.playing
+        }
+    }
+
+    fn ten_seconds() -> Sound {
+        Sound {
+            sample_rate: 100,
+            channels: 1,
+            loop_count: 0,
+            samples: vec![0; 1_000],
+        }
+    }
+
+    #[test]
+    fn parse_controls() {
+        assert_eq!(Control::parse("stop\n"), Some(Control::Stop));
+        assert_eq!(Control::parse("+5"), Some(Control::SeekBy(5.0)));
+        assert_eq!(Control::parse("-2.5"), Some(Control::SeekBy(-2.5)));
+        assert_eq!(
+            Control::parse("3"),
+            Some(Control::Seek(Duration::from_secs(3)))
+        );
+        assert_eq!(Control::parse("rewind"), None);
+        assert_eq!(Control::parse("+inf"), None);
+        assert_eq!(Control::parse("-NaN"), None);
+    }
+
+    #[test]
+    fn seek_while_playing() {
+        let mut recorder = Recorder::default();
+        let mut player = Player::new(&mut recorder, ten_seconds());
+
+        player.play().unwrap();
+        player.seek(Duration::from_secs(4)).unwrap();
+        player.seek(Duration::from_secs(60)).unwrap();
+        assert!(player.is_playing());
+
+        player.apply(Control::Stop).unwrap();
+        assert!(!player.is_playing());
+        assert_eq!(player.position(), Duration::from_secs(10));
+
+        assert_eq!(
+            recorder.played_from,
+            [
+                Duration::ZERO,
+                Duration::from_secs(4),
+                Duration::from_secs(10)
+            ]
+        );
+    }
+
+    #[test]
+    fn seek_while_stopped() {
+        let mut recorder = Recorder::default();
+        let mut player = Player::new(&mut recorder, ten_seconds());
+
+        player.seek(Duration::from_secs(5)).unwrap();
+        player.seek_by(-8.0).unwrap();
+        assert_eq!(player.position(), Duration::ZERO);
+        player.seek_by(2.0).unwrap();
+        assert!(!player.is_playing());
+        assert!(player.seek_by(1e300).is_err());
+
+        player.play().unwrap();
+        assert_eq!(recorder.played_from, [Duration::from_secs(2)]);
+    }
+
+    #[test]
+    fn null_plays_for_duration() {
+        let mut player = Player::new(Null::default(), ten_seconds());
+
+        player.play().unwrap();
+        assert!(player.is_playing());
+        player.seek(Duration::from_secs(10)).unwrap();
+        assert!(!player.is_playing());
+    }
+}
@@ -0,0 +1,281 @@
+use globset::{Glob, GlobMatcher};
+use regex::{bytes, Regex};
+
+use crate::{
+    mohawk::{self, ResourceID, TypeID},
+    Mohawk,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid glob: {0}")]
+    Glob(#[from] globset::Error),
+    #[error("invalid regex: {0}")]
+    Regex(#[from] regex::Error),
+    #[error("invalid hex byte: {0:?}")]
+    InvalidHex(String),
+}
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// What to look for in the resources
+pub enum Pattern {
+    Name(GlobMatcher),
+    NameRegex(Regex),
+    /// Raw content of the resource
+    Bytes(bytes::Regex),
+    /// Decoded text, such as comments and strings of pictures
+    Text(Regex),
+}
+
+impl Pattern {
+    pub fn name(glob: &str) -> Result<Self> {
+        Ok(Self::Name(Glob::new(glob)?.compile_matcher()))
+    }
+
+    pub fn name_regex(regex: &str) -> Result<Self> {
+        Ok(Self::NameRegex(Regex::new(regex)?))
+    }
+
+    /// Hex encoded bytes, whitespace is ignored and `??` matches any byte
+    pub fn bytes(hex: &str) -> Result<Self> {
+        let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+        // would match everywhere
+        if digits.is_empty() {
+            return Err(Error::InvalidHex(hex.to_string()));
+        }
+
+        let mut regex = "(?s-u)".to_string();
+        for pair in digits.chunks(2) {
+            let pair: String = pair.iter().collect();
+            if pair == "??" {
+                regex.push('.');
+                continue;
+            }
+
+            match u8::from_str_radix(&pair, 16) {
+                Ok(byte) if pair.len() == 2 => regex.push_str(&format!("\\x{:02X}", byte)),
+                _ => return Err(Error::InvalidHex(pair)),
+            }
+        }
+
+        Ok(Self::Bytes(bytes::Regex::new(&regex)?))
+    }
+
+    pub fn text(regex: &str) -> Result<Self> {
+        Ok(Self::Text(Regex::new(regex)?))
+    }
+
+    fn needs_data(&self) -> bool {
+        matches!(self, Self::Bytes(_) | Self::Text(_))
+    }
+}
+
+/// Resource matching a pattern
+pub struct Match {
+    pub type_id: TypeID,
+    pub id: ResourceID,
+    /// Position in the archive of the match, or of the resource for names
+    pub offset: u64,
+    /// What matched
+    pub found: String,
+}
+
+fn texts(type_id: &TypeID, data: &[u8]) -> Vec<(usize, String)> {
+    match type_id {
+        TypeID::PICT => pict_decoder::PICT::operations(data)
+            .into_iter()
+            .flatten()
+            .map_while(|op| op.ok())
+            .filter_map(|(offset, op)| Some((offset, op.text()?.to_string())))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Find every match of the patterns, ordered by type, ID and offset
+pub async fn search(mohawk: &Mohawk, patterns: &[Pattern]) -> mohawk::Result<Vec<Match>> {
+    let needs_data = patterns.iter().any(Pattern::needs_data);
+
+    let mut ret = Vec::new();
+    for (type_id, resources) in &mohawk.types {
+        for (id, resource) in resources {
+            let offset = resource.file.offset;
+            let name = resource.name.as_deref();
+            let data = match needs_data {
+                true => resource.data().await?,
+                false => Vec::new(),
+            };
+            let mut push = |at: u64, found: String| {
+                ret.push(Match {
+                    type_id: type_id.clone(),
+                    id: *id,
+                    offset: offset + at,
+                    found,
+                })
+            };
+
+            for pattern in patterns {
+                match pattern {
+                    // unnamed resources can't match a name
+                    Pattern::Name(glob) => {
+                        if let Some(name) = name.filter(|name| glob.is_match(name)) {
+                            push(0, name.to_string())
+                        }
+                    }
+                    Pattern::NameRegex(regex) => {
+                        if let Some(name) = name.filter(|name| regex.is_match(name)) {
+                            push(0, name.to_string())
+                        }
+                    }
+                    Pattern::Bytes(regex) => {
+                        for found in regex.find_iter(&data) {
+                            let hex: Vec<_> = found
+                                .as_bytes()
+                                .iter()
+                                .map(|b| format!("{:02X}", b))
+                                .collect();
+                            push(found.start() as u64, hex.join(" "));
+                        }
+                    }
+                    Pattern::Text(regex) => {
+                        for (at, text) in texts(type_id, &data) {
+                            if regex.is_match(&text) {
+                                push(at as u64, format!("{:?}", text));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ret.sort_by(|a, b| (&a.type_id, a.id, a.offset).cmp(&(&b.type_id, b.id, b.offset)));
+
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{build_mohawk, BuiltResource};
+
+    #[test]
+    fn parse_hex() {
+        let Pattern::Bytes(regex) = Pattern::bytes("4d 48??4B").unwrap() else {
+            panic!("not a bytes pattern");
+        };
+
+        assert!(regex.is_match(b"_MHWK"));
+        assert!(regex.is_match(b"MH\x00K"));
+        assert!(!regex.is_match(b"MHK"));
+        assert!(matches!(Pattern::bytes("4"), Err(Error::InvalidHex(_))));
+        assert!(matches!(Pattern::bytes("zz"), Err(Error::InvalidHex(_))));
+        assert!(matches!(Pattern::bytes(" "), Err(Error::InvalidHex(_))));
+    }
+
+    /// Version 2 picture of a single long comment
+    fn commented_pict(text: &[u8]) -> Vec<u8> {
+        let mut ret = vec![0; 512 + 2];
+        ret.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0, 1]);
+        ret.extend_from_slice(&[0x00, 0x11, 0x02, 0xFF]);
+        ret.extend_from_slice(&[0x0C, 0x00, 0xFF, 0xFE, 0, 0]);
+        ret.extend_from_slice(&[0, 72, 0, 0, 0, 72, 0, 0]);
+        ret.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0]);
+        ret.extend_from_slice(&[0x00, 0xA1, 0, 100]);
+        ret.extend_from_slice(&(text.len() as u16).to_be_bytes());
+        ret.extend_from_slice(text);
+        if text.len() % 2 == 1 {
+            ret.push(0);
+        }
+        ret.extend_from_slice(&[0x00, 0xFF]);
+
+        ret
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn search_picture_text() {
+        let pict = commented_pict(b"hello world");
+        let raw = build_mohawk(&[
+            BuiltResource {
+                type_id: *b"PICT",
+                id: 3,
+                name: None,
+                data: &pict,
+            },
+            BuiltResource {
+                type_id: *b"PICT",
+                id: 4,
+                name: Some("other"),
+                data: &commented_pict(b"goodbye"),
+            },
+        ]);
+        let dir = tempfile::tempdir().expect("to create temporary directory");
+        let path = dir.path().join("TEST.DAT");
+        std::fs::write(&path, raw).expect("to write archive");
+        let mohawk = crate::Mohawk::open(&path).await.expect("to open archive");
+
+        let found = search(
+            &mohawk,
+            &[
+                Pattern::text("wor").unwrap(),
+                Pattern::name_regex("^$").unwrap(),
+            ],
+        )
+        .await
+        .expect("to search");
+
+        let resource = &mohawk.types[&TypeID::PICT][&3];
+        assert_eq!(
+            found
+                .iter()
+                .map(|m| (m.id, m.offset - resource.file.offset, m.found.as_str()))
+                .collect::<Vec<_>>(),
+            [(3, 552, "\"hello world\"")]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn search_names_and_bytes() {
+        let raw = build_mohawk(&[
+            BuiltResource {
+                type_id: *b"tBMP",
+                id: 1,
+                name: Some("rocket"),
+                data: b"abcabc",
+            },
+            BuiltResource {
+                type_id: *b"tBMP",
+                id: 2,
+                name: Some("dock"),
+                data: b"xyz",
+            },
+        ]);
+        let dir = tempfile::tempdir().expect("to create temporary directory");
+        let path = dir.path().join("TEST.DAT");
+        std::fs::write(&path, raw).expect("to write archive");
+        let mohawk = crate::Mohawk::open(&path).await.expect("to open archive");
+
+        let found = search(
+            &mohawk,
+            &[
+                Pattern::name("*ock*").unwrap(),
+                Pattern::bytes("62 ?? 61").unwrap(),
+            ],
+        )
+        .await
+        .expect("to search");
+
+        let resources = &mohawk.types[&TypeID::from(*b"tBMP")];
+        assert_eq!(
+            found
+                .iter()
+                .map(|m| (
+                    m.id,
+                    m.offset - resources[&m.id].file.offset,
+                    m.found.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            [(1, 0, "rocket"), (1, 1, "62 63 61"), (2, 0, "dock")]
+        );
+    }
+}
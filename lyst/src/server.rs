@@ -0,0 +1,294 @@
+use std::{collections::BTreeMap, fmt::Write, path::PathBuf, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+use tokio::task;
+use tracing::warn;
+
+use crate::{
+    convert,
+    mohawk::{self, Resource, ResourceID, TypeID},
+    Mohawk,
+};
+
+/// Shown bytes of the hex view
+const HEX_VIEW_LIMIT: usize = 64 * 1024;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("archive not found")]
+    ArchiveNotFound,
+    #[error("type not found")]
+    TypeNotFound,
+    #[error("resource not found")]
+    ResourceNotFound,
+    #[error("read: {0}")]
+    Read(#[from] mohawk::Error),
+    #[error("convert: {0}")]
+    Convert(#[from] convert::Error),
+}
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::ArchiveNotFound | Self::TypeNotFound | Self::ResourceNotFound => {
+                StatusCode::NOT_FOUND
+            }
+            Self::Read(_) | Self::Convert(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        (status, self.to_string()).into_response()
+    }
+}
+
+/// Opened archives, by file name
+type Archives = Arc<BTreeMap<String, Mohawk>>;
+
+#[derive(Serialize)]
+struct ResourceEntry {
+    id: ResourceID,
+    name: Option<String>,
+    offset: u64,
+    size: u32,
+}
+
+/// Resources of every type, sorted by ID
+fn directory(mohawk: &Mohawk) -> BTreeMap<String, Vec<ResourceEntry>> {
+    mohawk
+        .types
+        .iter()
+        .map(|(type_id, resources)| {
+            let mut entries: Vec<_> = resources
+                .iter()
+                .map(|(id, resource)| ResourceEntry {
+                    id: *id,
+                    name: resource.name.clone(),
+                    offset: resource.file.offset,
+                    size: resource.file.size,
+                })
+                .collect();
+            entries.sort_unstable_by_key(|entry| entry.id);
+
+            (type_id.to_string(), entries)
+        })
+        .collect()
+}
+
+fn escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn page(title: &str, body: &str) -> Html<String> {
+    Html(format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0} - lyst</title></head>\
+         <body><h1>{0}</h1>{1}</body></html>\n",
+        escape(title),
+        body
+    ))
+}
+
+/// Offset, bytes and printable characters, 16 bytes per line
+fn hex_view(data: &[u8]) -> String {
+    let mut ret = String::new();
+
+    for (i, line) in data.chunks(16).enumerate() {
+        let _ = write!(ret, "{:08X} ", i * 16);
+        for byte in line {
+            let _ = write!(ret, " {:02X}", byte);
+        }
+        ret.push_str(&"   ".repeat(16 - line.len()));
+        ret.push_str("  ");
+        ret.extend(line.iter().map(|&b| match b {
+            0x20..=0x7E => b as char,
+            _ => '.',
+        }));
+        ret.push('\n');
+    }
+
+    ret
+}
+
+fn find<'a>(
+    archives: &'a Archives,
+    archive: &str,
+    type_id: &str,
+    id: ResourceID,
+) -> Result<(TypeID, &'a Resource)> {
+    let mohawk = archives.get(archive).ok_or(Error::ArchiveNotFound)?;
+    let type_id =
+        TypeID::from(<[u8; 4]>::try_from(type_id.as_bytes()).map_err(|_| Error::TypeNotFound)?);
+
+    let resource = mohawk
+        .types
+        .get(&type_id)
+        .ok_or(Error::TypeNotFound)?
+        .get(&id)
+        .ok_or(Error::ResourceNotFound)?;
+
+    Ok((type_id, resource))
+}
+
+async fn index(State(archives): State<Archives>) -> Html<String> {
+    let mut body = "<ul>".to_string();
+    for name in archives.keys() {
+        let name = escape(name);
+        let _ = write!(body, "<li><a href=\"/archives/{0}\">{0}</a></li>", name);
+    }
+    body.push_str("</ul><p><a href=\"/api/archives\">JSON</a></p>");
+
+    page("Archives", &body)
+}
+
+async fn api_archives(
+    State(archives): State<Archives>,
+) -> Json<BTreeMap<String, BTreeMap<String, Vec<ResourceEntry>>>> {
+    Json(
+        archives
+            .iter()
+            .map(|(name, mohawk)| (name.clone(), directory(mohawk)))
+            .collect(),
+    )
+}
+
+async fn archive(
+    State(archives): State<Archives>,
+    Path(archive): Path<String>,
+) -> Result<Html<String>> {
+    let mohawk = archives.get(&archive).ok_or(Error::ArchiveNotFound)?;
+    let archive = escape(&archive);
+
+    let mut body = String::new();
+    for (type_id, entries) in directory(mohawk) {
+        let type_id = escape(&type_id);
+        let _ = write!(body, "<h2>{}</h2><ul>", type_id);
+        for entry in entries {
+            let _ = write!(
+                body,
+                "<li><a href=\"/archives/{}/{}/{}\">{} {}</a> ({} bytes)</li>",
+                archive,
+                type_id,
+                entry.id,
+                entry.id,
+                escape(entry.name.as_deref().unwrap_or("")),
+                entry.size,
+            );
+        }
+        body.push_str("</ul>");
+    }
+
+    Ok(page(&archive, &body))
+}
+
+async fn resource(
+    State(archives): State<Archives>,
+    Path((archive, type_id, id)): Path<(String, String, ResourceID)>,
+) -> Result<Html<String>> {
+    let (parsed_type_id, resource) = find(&archives, &archive, &type_id, id)?;
+    let content = format!(
+        "/archives/{}/{}/{}/content",
+        escape(&archive),
+        escape(&type_id),
+        id
+    );
+
+    let body = match parsed_type_id {
+        TypeID::PICT => format!("<img src=\"{}\">", content),
+        TypeID::MSND => format!("<audio controls src=\"{}\"></audio>", content),
+        TypeID::Unknown(_) => {
+            let data = resource.data().await?;
+            let shown = &data[..data.len().min(HEX_VIEW_LIMIT)];
+            let mut ret = format!("<pre>{}</pre>", escape(&hex_view(shown)));
+            if shown.len() < data.len() {
+                let _ = write!(ret, "<p>first {} of {} bytes</p>", shown.len(), data.len());
+            }
+            ret
+        }
+    };
+
+    let title = format!(
+        "{} {} {} {}",
+        archive,
+        type_id,
+        id,
+        resource.name.as_deref().unwrap_or("")
+    );
+    Ok(page(title.trim_end(), &body))
+}
+
+async fn content(
+    State(archives): State<Archives>,
+    Path((archive, type_id, id)): Path<(String, String, ResourceID)>,
+) -> Result<Response> {
+    let (type_id, resource) = find(&archives, &archive, &type_id, id)?;
+
+    let raw = resource.data().await?;
+    let converted = task::spawn_blocking(move || convert::convert(&type_id, raw))
+        .await
+        .expect("conversion to not panic")?;
+    let content_type = match converted.extension {
+        "png" => "image/png",
+        "wav" => "audio/wav",
+        _ => "application/octet-stream",
+    };
+
+    Ok(([(header::CONTENT_TYPE, content_type)], converted.data).into_response())
+}
+
+/// Open the archives, keying them by their file name and skipping the ones failing to open
+pub async fn open_archives(paths: Vec<PathBuf>) -> BTreeMap<String, Mohawk> {
+    let mut ret = BTreeMap::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned();
+        match Mohawk::open(&path).await {
+            Ok(mohawk) => {
+                ret.insert(name, mohawk);
+            }
+            Err(e) => warn!("skip {}: {}", path.display(), e),
+        }
+    }
+
+    ret
+}
+
+/// Routes to browse the given archives, keyed by their file name
+pub fn router(archives: BTreeMap<String, Mohawk>) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/api/archives", get(api_archives))
+        .route("/archives/:archive", get(archive))
+        .route("/archives/:archive/:type_id/:id", get(resource))
+        .route("/archives/:archive/:type_id/:id/content", get(content))
+        .with_state(Arc::new(archives))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_lines() {
+        let view = hex_view(b"0123456789abcdefXY\x00");
+
+        assert_eq!(
+            view.lines().collect::<Vec<_>>(),
+            [
+                "00000000  30 31 32 33 34 35 36 37 38 39 61 62 63 64 65 66  0123456789abcdef",
+                "00000010  58 59 00                                         XY.",
+            ]
+        );
+    }
+}
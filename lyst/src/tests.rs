@@ -3,6 +3,10 @@ use std::path::{Path, PathBuf};
 use stream::StreamExt;
 use tokio_stream::{self as stream, Stream};
 
+#[path = "../tests/common/mod.rs"]
+mod common;
+pub use common::{build_mohawk, BuiltResource};
+
 static MYST_INSTALL_DIR: &str = "myst";
 
 pub fn get_known_files() -> impl Stream<Item = PathBuf> {
@@ -0,0 +1,156 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::{
+    mohawk::{msnd::Sound, ResourceID, TypeID},
+    Mohawk,
+};
+
+/// Outcome of decoding every supported resource of an install
+#[derive(Serialize)]
+pub struct Report {
+    pub archives: Vec<ArchiveReport>,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveReport {
+    pub path: PathBuf,
+    /// Reason the archive couldn't be opened, in which case there is no type
+    pub error: Option<String>,
+    pub types: BTreeMap<String, TypeReport>,
+}
+
+#[derive(Default, Serialize)]
+pub struct TypeReport {
+    pub success: usize,
+    pub failure: usize,
+    pub failures: Vec<Failure>,
+}
+
+#[derive(Serialize)]
+pub struct Failure {
+    pub id: ResourceID,
+    pub error: String,
+}
+
+impl Report {
+    pub fn failure_count(&self) -> usize {
+        self.archives
+            .iter()
+            .map(|archive| {
+                archive.error.iter().count()
+                    + archive.types.values().map(|t| t.failure).sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+/// Decode the resource, none if its type isn't supported
+fn decode(type_id: &TypeID, data: &[u8]) -> Option<Result<(), String>> {
+    match type_id {
+        TypeID::PICT => Some(
+            pict_decoder::PICT::parse(data)
+                .map(drop)
+                .map_err(|e| e.to_string()),
+        ),
+        TypeID::MSND => Some(Sound::parse(data).map(drop).map_err(|e| e.to_string())),
+        TypeID::Unknown(_) => None,
+    }
+}
+
+async fn verify_archive(path: PathBuf) -> ArchiveReport {
+    let mut ret = ArchiveReport {
+        path,
+        error: None,
+        types: BTreeMap::new(),
+    };
+
+    let mohawk = match Mohawk::open(&ret.path).await {
+        Ok(mohawk) => mohawk,
+        Err(e) => {
+            ret.error = Some(e.to_string());
+            return ret;
+        }
+    };
+
+    for (type_id, resources) in &mohawk.types {
+        let mut sorted_resources: Vec<_> = resources.iter().collect();
+        sorted_resources.sort_unstable_by_key(|(id, _)| *id);
+
+        for (id, resource) in sorted_resources {
+            let decoded = match resource.data().await {
+                Ok(data) => decode(type_id, &data),
+                Err(e) => Some(Err(e.to_string())),
+            };
+            let Some(decoded) = decoded else {
+                continue;
+            };
+
+            let report = ret.types.entry(type_id.to_string()).or_default();
+            match decoded {
+                Ok(()) => report.success += 1,
+                Err(error) => {
+                    report.failure += 1;
+                    report.failures.push(Failure { id: *id, error });
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+/// Open every archive of the install and decode every resource of a supported type
+pub async fn verify(archives: Vec<PathBuf>) -> Report {
+    let mut ret = Report {
+        archives: Vec::with_capacity(archives.len()),
+    };
+
+    for archive in archives {
+        ret.archives.push(verify_archive(archive).await);
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{build_mohawk, BuiltResource};
+
+    #[test_log::test(tokio::test)]
+    async fn count_per_type() {
+        let dir = tempfile::tempdir().expect("to create temporary directory");
+        let path = dir.path().join("TEST.DAT");
+        std::fs::write(
+            &path,
+            build_mohawk(&[
+                BuiltResource {
+                    type_id: *b"PICT",
+                    id: 1,
+                    name: None,
+                    data: b"not a picture",
+                },
+                BuiltResource {
+                    type_id: *b"tBMP",
+                    id: 1,
+                    name: None,
+                    data: b"",
+                },
+            ]),
+        )
+        .expect("to write archive");
+        let missing = dir.path().join("MISSING.DAT");
+
+        let report = verify(vec![path, missing]).await;
+
+        assert_eq!(report.failure_count(), 2);
+        assert!(report.archives[1].error.is_some());
+
+        let types = &report.archives[0].types;
+        assert_eq!(types.keys().collect::<Vec<_>>(), ["PICT"]);
+        assert_eq!(types["PICT"].success, 0);
+        assert_eq!(types["PICT"].failures[0].id, 1);
+    }
+}
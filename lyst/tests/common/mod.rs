@@ -0,0 +1,87 @@
+//! Fixtures shared by the unit and the integration tests
+
+pub struct BuiltResource<'a> {
+    pub type_id: [u8; 4],
+    pub id: u16,
+    pub name: Option<&'a str>,
+    pub data: &'a [u8],
+}
+
+/// Build a Mohawk archive containing the given resources, types are expected to be contiguous
+pub fn build_mohawk(resources: &[BuiltResource]) -> Vec<u8> {
+    const HEADERS_SIZE: usize = 8 + 20;
+
+    let mut types: Vec<(&[u8; 4], Vec<_>)> = Vec::new();
+    for (file_id, resource) in resources.iter().enumerate() {
+        match types.last_mut() {
+            Some((type_id, entries)) if **type_id == resource.type_id => {
+                entries.push((file_id, resource))
+            }
+            _ => types.push((&resource.type_id, vec![(file_id, resource)])),
+        }
+    }
+
+    let mut files = Vec::new();
+    let mut file_table = Vec::new();
+    file_table.extend_from_slice(&(resources.len() as u32).to_be_bytes());
+    for resource in resources {
+        file_table.extend_from_slice(&((HEADERS_SIZE + files.len()) as u32).to_be_bytes());
+        file_table.extend_from_slice(&(resource.data.len() as u16).to_be_bytes());
+        file_table.push((resource.data.len() >> 16) as u8);
+        file_table.extend_from_slice(&[0, 0, 0]); // flag and unknown
+        files.extend_from_slice(resource.data);
+    }
+
+    // resource directory, starting with name list offset then type table
+    let type_table_size = 2 + types.len() * 8;
+    let mut tables = Vec::new();
+    let mut type_table = Vec::new();
+    type_table.extend_from_slice(&(types.len() as u16).to_be_bytes());
+    let mut name_list = Vec::new();
+    for (type_id, entries) in &types {
+        let resource_table_offset = 2 + type_table_size + tables.len();
+        tables.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+        for (file_id, resource) in entries {
+            tables.extend_from_slice(&resource.id.to_be_bytes());
+            tables.extend_from_slice(&(*file_id as u16 + 1).to_be_bytes());
+        }
+
+        let name_table_offset = 2 + type_table_size + tables.len();
+        let named: Vec<_> = entries.iter().filter(|(_, r)| r.name.is_some()).collect();
+        tables.extend_from_slice(&(named.len() as u16).to_be_bytes());
+        for (file_id, resource) in named {
+            tables.extend_from_slice(&(name_list.len() as u16).to_be_bytes());
+            tables.extend_from_slice(&(*file_id as u16).to_be_bytes());
+            name_list.extend_from_slice(resource.name.unwrap().as_bytes());
+            name_list.push(0);
+        }
+
+        type_table.extend_from_slice(*type_id);
+        type_table.extend_from_slice(&(resource_table_offset as u16).to_be_bytes());
+        type_table.extend_from_slice(&(name_table_offset as u16).to_be_bytes());
+    }
+
+    let name_list_offset = 2 + type_table_size + tables.len();
+    let file_table_offset = name_list_offset + name_list.len();
+    let resource_dir_offset = HEADERS_SIZE + files.len();
+    let total_size = resource_dir_offset + file_table_offset + file_table.len();
+
+    let mut ret = Vec::with_capacity(total_size);
+    ret.extend_from_slice(b"MHWK");
+    ret.extend_from_slice(&(total_size as u32 - 8).to_be_bytes());
+    ret.extend_from_slice(b"RSRC");
+    ret.extend_from_slice(&0x100u16.to_be_bytes());
+    ret.extend_from_slice(&1u16.to_be_bytes());
+    ret.extend_from_slice(&(total_size as u32).to_be_bytes());
+    ret.extend_from_slice(&(resource_dir_offset as u32).to_be_bytes());
+    ret.extend_from_slice(&(file_table_offset as u16).to_be_bytes());
+    ret.extend_from_slice(&(file_table.len() as u16).to_be_bytes());
+    ret.extend_from_slice(&files);
+    ret.extend_from_slice(&(name_list_offset as u16).to_be_bytes());
+    ret.extend_from_slice(&type_table);
+    ret.extend_from_slice(&tables);
+    ret.extend_from_slice(&name_list);
+    ret.extend_from_slice(&file_table);
+
+    ret
+}
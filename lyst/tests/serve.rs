@@ -0,0 +1,107 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+mod common;
+use common::{build_mohawk, BuiltResource};
+
+struct Response {
+    status: u16,
+    head: String,
+    body: Vec<u8>,
+}
+
+async fn get(addr: SocketAddr, path: &str) -> Response {
+    let mut stream = TcpStream::connect(addr).await.expect("to connect");
+    stream
+        .write_all(
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .as_bytes(),
+        )
+        .await
+        .expect("to send request");
+
+    let mut raw = Vec::new();
+    stream
+        .read_to_end(&mut raw)
+        .await
+        .expect("to read response");
+
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .expect("end of headers");
+    let head = String::from_utf8_lossy(&raw[..split]).into_owned();
+    let status = head
+        .split(' ')
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .expect("status code");
+
+    Response {
+        status,
+        head,
+        body: raw[split + 4..].to_vec(),
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn browse_over_loopback() {
+    let dir = tempfile::tempdir().expect("to create temporary directory");
+    std::fs::write(
+        dir.path().join("TEST.DAT"),
+        build_mohawk(&[
+            BuiltResource {
+                type_id: *b"tBMP",
+                id: 3,
+                name: None,
+                data: b"abc",
+            },
+            BuiltResource {
+                type_id: *b"tBMP",
+                id: 1,
+                name: None,
+                data: b"<hi>",
+            },
+        ]),
+    )
+    .expect("to write archive");
+    std::fs::write(dir.path().join("BROKEN.DAT"), b"not an archive").expect("to write archive");
+
+    let archives = lyst::install::find_archives(dir.path())
+        .await
+        .expect("to find archives");
+    let router = lyst::server::router(lyst::server::open_archives(archives).await);
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .expect("to bind");
+    let addr = listener.local_addr().expect("to have an address");
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    let directory = get(addr, "/api/archives").await;
+    assert_eq!(directory.status, 200);
+    let directory: serde_json::Value = serde_json::from_slice(&directory.body).expect("to be JSON");
+    assert_eq!(directory["TEST.DAT"]["tBMP"][0]["id"], 1);
+    assert_eq!(directory["TEST.DAT"]["tBMP"][1]["size"], 3);
+
+    let hex = get(addr, "/archives/TEST.DAT/tBMP/1").await;
+    assert_eq!(hex.status, 200);
+    let hex = String::from_utf8(hex.body).expect("to be UTF-8");
+    assert!(hex.contains("3C 68 69 3E"));
+    assert!(hex.contains("&lt;hi&gt;"));
+
+    let content = get(addr, "/archives/TEST.DAT/tBMP/3/content").await;
+    assert_eq!(content.status, 200);
+    assert!(content
+        .head
+        .to_ascii_lowercase()
+        .contains("content-type: application/octet-stream"));
+    assert_eq!(content.body, b"abc");
+
+    assert_eq!(get(addr, "/archives/OTHER.DAT").await.status, 404);
+    assert_eq!(get(addr, "/archives/BROKEN.DAT").await.status, 404);
+    assert_eq!(get(addr, "/archives/TEST.DAT/tBMP/2").await.status, 404);
+}
@@ -40,7 +40,7 @@ fn bench_roundtrip(mut group: BenchmarkGroup<'_, WallTime>, get_input: impl Fn(u
 
 fn single_repeated_byte(c: &mut Criterion) {
     bench_roundtrip(c.benchmark_group("single_repeated_byte"), |size| {
-        iter::repeat(0u8).take(size).collect()
+        iter::repeat_n(0u8, size).collect()
     })
 }
 
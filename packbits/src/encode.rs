@@ -172,8 +172,7 @@ mod tests {
     #[test]
     fn long_repeated() {
         test_encode_to(
-            iter::repeat(b'a')
-                .take(128 + 16)
+            iter::repeat_n(b'a', 128 + 16)
                 .collect::<Vec<_>>()
                 .as_slice(),
             b"\x81a\xF1a",
@@ -45,7 +45,7 @@ mod tests {
 
     #[test]
     fn roundtrip_repeated_byte() {
-        roundtrip(iter::repeat(0xAB).take(INPUT_SIZE).collect::<Vec<_>>())
+        roundtrip(iter::repeat_n(0xAB, INPUT_SIZE).collect::<Vec<_>>())
     }
 
     #[test]
@@ -0,0 +1,991 @@
+use std::collections::HashMap;
+
+use encoding_rs::WINDOWS_1252;
+use tracing::warn;
+
+use crate::{
+    font::{self, Font, Fonts},
+    image::{Image, PixelFormat},
+    operation::Operation,
+    pattern::{Ink, Pattern},
+    point::Point,
+    rectangle::Rectangle,
+    region::{intersect, Mask, Region},
+    shape::{Polygon, Shape, Verb},
+    Result,
+};
+
+/// Left, top, right and bottom edges
+type Area = (i32, i32, i32, i32);
+
+/// Mode copying the pattern, as used to erase and fill
+const PAT_COPY: u16 = 8;
+
+/// Mode drawing only the glyphs, as used by default for text
+const SRC_OR: u16 = 1;
+
+/// Size of the system font, used for text of size zero
+const DEFAULT_TEXT_SIZE: u16 = 12;
+
+/// Styles of TxFace, as bits
+mod face {
+    pub(super) const BOLD: u8 = 1 << 0;
+    pub(super) const ITALIC: u8 = 1 << 1;
+    pub(super) const UNDERLINE: u8 = 1 << 2;
+    pub(super) const OUTLINE: u8 = 1 << 3;
+    pub(super) const SHADOW: u8 = 1 << 4;
+    pub(super) const CONDENSE: u8 = 1 << 5;
+    pub(super) const EXTEND: u8 = 1 << 6;
+}
+
+/// Colors of FgColor and BkColor, named after the Macintosh constants
+fn old_color(value: u32) -> [u8; 3] {
+    match value {
+        30 => [0xFF, 0xFF, 0xFF],
+        69 => [0xFF, 0xFF, 0x00],
+        137 => [0xFF, 0x00, 0xFF],
+        205 => [0xFF, 0x00, 0x00],
+        273 => [0x00, 0xFF, 0xFF],
+        341 => [0x00, 0xFF, 0x00],
+        409 => [0x00, 0x00, 0xFF],
+        _ => [0x00, 0x00, 0x00],
+    }
+}
+
+/// High bytes of 16 bits components
+fn rgb(color: &[u16; 3]) -> [u8; 3] {
+    color.map(|c| (c >> 8) as u8)
+}
+
+/// New color of a pixel drawn with a pattern mode, the not variants swapping the pattern's colors
+fn transfer(mode: u16, ink: Ink, fore: [u8; 3], back: [u8; 3], current: [u8; 3]) -> [u8; 3] {
+    let is_not = mode & 0x04 != 0;
+    let (is_fore, color) = match (ink, is_not) {
+        (Ink::Fore, false) | (Ink::Back, true) => (true, fore),
+        (Ink::Fore, true) | (Ink::Back, false) => (false, back),
+        (Ink::Color(color), false) => (true, color),
+        (Ink::Color(color), true) => (true, color.map(|c| !c)),
+    };
+
+    match (mode & 0x03, is_fore) {
+        // copy
+        (0, _) => color,
+        // or
+        (1, true) => color,
+        // xor
+        (2, true) => current.map(|c| !c),
+        // bic
+        (3, true) => back,
+        _ => current,
+    }
+}
+
+/// New color of a pixel drawn from a bitmap, the boolean modes taking black as set bits
+fn transfer_source(
+    mode: u16,
+    source: [u8; 3],
+    (fore, back, op_color): ([u8; 3], [u8; 3], [u8; 3]),
+    current: [u8; 3],
+) -> [u8; 3] {
+    let each =
+        |f: &dyn Fn(u8, u8, u8) -> u8| [0, 1, 2].map(|i| f(source[i], current[i], op_color[i]));
+    // black and white sources take the colors of the foreground and background
+    let colorized = [0, 1, 2].map(|i| {
+        let s = source[i] as u16;
+        ((fore[i] as u16 * (0xFF - s) + back[i] as u16 * s) / 0xFF) as u8
+    });
+
+    match mode {
+        // srcCopy, ditherCopy
+        0 | 64 => colorized,
+        // srcOr, srcXor, srcBic
+        1 => each(&|s, c, _| s & c),
+        2 => each(&|s, c, _| !(s ^ c)),
+        3 => each(&|s, c, _| c | !s),
+        // notSrcCopy, notSrcOr, notSrcXor, notSrcBic
+        4 => colorized.map(|c| !c),
+        5 => each(&|s, c, _| !s & c),
+        6 => each(&|s, c, _| s ^ c),
+        7 => each(&|s, c, _| c | s),
+        // blend, weighted by the op color
+        32 => each(&|s, c, w| ((s as u32 * w as u32 + c as u32 * (0xFF - w as u32)) / 0xFF) as u8),
+        // addPin, addOver, subPin, limited by the op color or wrapping
+        33 => each(&|s, c, pin| s.saturating_add(c).min(pin)),
+        34 => each(&|s, c, _| s.wrapping_add(c)),
+        35 => each(&|s, c, pin| c.saturating_sub(s).max(pin)),
+        // transparent, the background color letting the destination through
+        36 if source == back => current,
+        36 => source,
+        // adMax, subOver, adMin
+        37 => each(&|s, c, _| s.max(c)),
+        38 => each(&|s, c, _| c.wrapping_sub(s)),
+        39 => each(&|s, c, _| s.min(c)),
+        _ => {
+            warn!("unsupported transfer mode {}, copying", mode);
+            colorized
+        }
+    }
+}
+
+/// Whether the pixel is inside the rectangle with corners rounded by ovals of the given size
+fn in_rounded_rect(
+    (left, top, right, bottom): Area,
+    (oval_width, oval_height): (i32, i32),
+    x: i32,
+    y: i32,
+) -> bool {
+    let rx = oval_width.min(right - left) as f64 / 2.;
+    let ry = oval_height.min(bottom - top) as f64 / 2.;
+    if rx <= 0. || ry <= 0. {
+        return true;
+    }
+
+    let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+    let dx = (left as f64 + rx - px)
+        .max(px - (right as f64 - rx))
+        .max(0.);
+    let dy = (top as f64 + ry - py)
+        .max(py - (bottom as f64 - ry))
+        .max(0.);
+
+    (dx / rx).powi(2) + (dy / ry).powi(2) <= 1.
+}
+
+/// Whether the pixel is in the angles, clockwise from the top with 45 degrees at the corners
+fn in_wedge((left, top, right, bottom): Area, start: i16, angle: i16, x: i32, y: i32) -> bool {
+    let (start, angle) = match angle {
+        angle if angle.unsigned_abs() >= 360 => return true,
+        angle if angle < 0 => (start as f64 + angle as f64, -angle as f64),
+        angle => (start as f64, angle as f64),
+    };
+
+    let dx = (x as f64 + 0.5 - (left + right) as f64 / 2.) / (right - left).max(1) as f64;
+    let dy = (y as f64 + 0.5 - (top + bottom) as f64 / 2.) / (bottom - top).max(1) as f64;
+    let degrees = dx.atan2(-dy).to_degrees();
+
+    (degrees - start).rem_euclid(360.) <= angle
+}
+
+/// Whether the pixel is inside the polygon, crossing its outline an odd number of times
+fn in_polygon(points: &[Point], x: i32, y: i32) -> bool {
+    let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+
+    let mut ret = false;
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        let (ax, ay, bx, by) = (a.x as f64, a.y as f64, b.x as f64, b.y as f64);
+        if (ay > py) != (by > py) && px < ax + (py - ay) * (bx - ax) / (by - ay) {
+            ret = !ret;
+        }
+    }
+
+    ret
+}
+
+/// Pixels of the picture's frame
+struct Surface {
+    frame: Rectangle,
+    data: Vec<u8>,
+}
+
+impl Surface {
+    /// Replace the color of the pixels of the mask inside the frame
+    fn apply(&mut self, mask: &Mask, color_of: impl Fn(i32, i32, [u8; 3]) -> [u8; 3]) {
+        let (left, top, _, _) = self.frame.area();
+        let (width, height) = (self.frame.width() as i32, self.frame.height() as i32);
+
+        for (x, y) in mask.points() {
+            let (column, row) = (x - left, y - top);
+            if !(0..width).contains(&column) || !(0..height).contains(&row) {
+                continue;
+            }
+
+            let offset = (row * width + column) as usize * 3;
+            let current = [
+                self.data[offset],
+                self.data[offset + 1],
+                self.data[offset + 2],
+            ];
+            self.data[offset..offset + 3].copy_from_slice(&color_of(x, y, current));
+        }
+    }
+}
+
+/// QuickDraw state, rasterizing drawing operations on the frame
+pub(crate) struct Canvas<'a> {
+    surface: Surface,
+    /// Count of drawing operations
+    draws: usize,
+    /// Visible pixels, in frame coordinates
+    clip: Option<Mask>,
+    /// Offset from drawing to frame coordinates
+    origin: (i32, i32),
+    pen_location: (i32, i32),
+    pen_size: (i32, i32),
+    pen_mode: u16,
+    pen_pattern: Pattern,
+    fill_pattern: Pattern,
+    back_pattern: Pattern,
+    fore_color: [u8; 3],
+    back_color: [u8; 3],
+    /// Weight of blends and limit of additions and subtractions
+    op_color: [u8; 3],
+    oval_size: (i32, i32),
+    last_rect: Rectangle,
+    last_polygon: Option<Polygon>,
+    last_region: Option<Region>,
+    fonts: &'a Fonts,
+    embedded_font: Font,
+    /// Names given by FontName
+    font_names: HashMap<i16, String>,
+    text_font: i16,
+    text_face: u8,
+    text_mode: u16,
+    text_size: u16,
+    /// Horizontal and vertical scaling
+    text_ratio: (f64, f64),
+    /// Fixed point
+    space_extra: i32,
+    char_extra: i16,
+    /// Where the last text was drawn, as text opcodes are relative to it
+    text_location: (i32, i32),
+}
+
+impl<'a> Canvas<'a> {
+    /// White frame, with the default black pen, writing with the given fonts
+    pub(crate) fn new(frame: Rectangle, fonts: &'a Fonts) -> Self {
+        Self {
+            surface: Surface {
+                frame,
+                data: vec![0xFF; frame.width() * frame.height() * 3],
+            },
+            draws: 0,
+            clip: None,
+            origin: (0, 0),
+            pen_location: (0, 0),
+            pen_size: (1, 1),
+            pen_mode: PAT_COPY,
+            pen_pattern: Pattern::BLACK,
+            fill_pattern: Pattern::BLACK,
+            back_pattern: Pattern::WHITE,
+            fore_color: [0x00; 3],
+            back_color: [0xFF; 3],
+            op_color: [0xFF; 3],
+            oval_size: (0, 0),
+            last_rect: frame,
+            last_polygon: None,
+            last_region: None,
+            fonts,
+            embedded_font: Font::embedded(),
+            font_names: HashMap::new(),
+            text_font: 0,
+            text_face: 0,
+            text_mode: SRC_OR,
+            text_size: 0,
+            text_ratio: (1., 1.),
+            space_extra: 0,
+            char_extra: 0,
+            text_location: (0, 0),
+        }
+    }
+
+    /// Visible pixels, in frame coordinates
+    pub(crate) fn clip(&self) -> Option<&Mask> {
+        self.clip.as_ref()
+    }
+
+    /// Whether any shape was drawn
+    pub(crate) fn is_drawn(&self) -> bool {
+        self.draws > 0
+    }
+
+    /// Count of drawing operations, to know whether anything was drawn since
+    pub(crate) fn draws(&self) -> usize {
+        self.draws
+    }
+
+    pub(crate) fn into_image(self) -> Result<Image> {
+        let frame = self.surface.frame;
+
+        Image::new(
+            frame.width(),
+            frame.height(),
+            PixelFormat::RGB24,
+            self.surface.data,
+        )
+    }
+
+    /// Drawing coordinates where pixels might end in the frame, with a margin for the pen
+    fn area(&self) -> Area {
+        let (left, top, right, bottom) = self.surface.frame.area();
+        let (x, y) = self.origin;
+        let (width, height) = self.pen_size;
+
+        (
+            left + x - width,
+            top + y - height,
+            right + x + width,
+            bottom + y + height,
+        )
+    }
+
+    /// Update the state or draw the operation, ignoring the others
+    pub(crate) fn execute(&mut self, op: &Operation) {
+        match op {
+            Operation::Clip(region) => {
+                let (x, y) = self.origin;
+                self.clip = Some(region.mask(self.area()).offset(-x, -y));
+            }
+            Operation::BkPat(pattern) | Operation::BkPixPat(pattern) => {
+                self.back_pattern = pattern.clone()
+            }
+            Operation::PnPat(pattern) | Operation::PnPixPat(pattern) => {
+                self.pen_pattern = pattern.clone()
+            }
+            Operation::FillPat(pattern) | Operation::FillPixPat(pattern) => {
+                self.fill_pattern = pattern.clone()
+            }
+            Operation::PnSize(size) => self.pen_size = (size.x.max(0) as i32, size.y.max(0) as i32),
+            Operation::PnMode(mode) => self.pen_mode = *mode,
+            Operation::OvSize(size) => self.oval_size = (size.x as i32, size.y as i32),
+            Operation::Origin { dh, dv } => {
+                self.origin = (self.origin.0 + *dh as i32, self.origin.1 + *dv as i32)
+            }
+            Operation::FgColor(color) => self.fore_color = old_color(*color),
+            Operation::BkColor(color) => self.back_color = old_color(*color),
+            Operation::RGBFgCol(color) => self.fore_color = rgb(color),
+            Operation::RGBBkCol(color) => self.back_color = rgb(color),
+            Operation::OpColor(color) => self.op_color = rgb(color),
+            Operation::Line { from, to } => {
+                self.line((from.x as i32, from.y as i32), (to.x as i32, to.y as i32))
+            }
+            Operation::LineFrom(to) => self.line(self.pen_location, (to.x as i32, to.y as i32)),
+            Operation::ShortLine { from, dh, dv } => {
+                let from = (from.x as i32, from.y as i32);
+                self.line(from, (from.0 + *dh as i32, from.1 + *dv as i32))
+            }
+            Operation::ShortLineFrom { dh, dv } => {
+                let (x, y) = self.pen_location;
+                self.line((x, y), (x + *dh as i32, y + *dv as i32))
+            }
+            Operation::Shape { verb, shape } => self.shape(*verb, shape),
+            Operation::TxFont(font) => self.text_font = *font,
+            Operation::TxFace(face) => self.text_face = *face,
+            Operation::TxMode(mode) => self.text_mode = *mode,
+            Operation::TxSize(size) => self.text_size = (*size).max(0) as u16,
+            Operation::TxRatio {
+                numerator,
+                denominator,
+            } => {
+                let ratio = |n: i16, d: i16| match d {
+                    0 => 1.,
+                    d => n as f64 / d as f64,
+                };
+                self.text_ratio = (
+                    ratio(numerator.x, denominator.x),
+                    ratio(numerator.y, denominator.y),
+                );
+            }
+            Operation::SpExtra(extra) => self.space_extra = *extra,
+            Operation::ChExtra(extra) => self.char_extra = *extra,
+            Operation::FontName { id, name } => {
+                self.font_names.insert(*id, name.clone());
+            }
+            Operation::LongText { location, text } => {
+                self.text((location.x as i32, location.y as i32), text)
+            }
+            Operation::DHText { dh, text } => {
+                let (x, y) = self.text_location;
+                self.text((x + *dh as i32, y), text)
+            }
+            Operation::DVText { dv, text } => {
+                let (x, y) = self.text_location;
+                self.text((x, y + *dv as i32), text)
+            }
+            Operation::DHDVText { dh, dv, text } => {
+                let (x, y) = self.text_location;
+                self.text((x + *dh as i32, y + *dv as i32), text)
+            }
+            _ => {}
+        }
+    }
+
+    /// Font of the current number and its size, the embedded one if none is given for it
+    fn font(&self) -> (u16, &Font) {
+        let size = match self.text_size {
+            0 => DEFAULT_TEXT_SIZE,
+            size => size,
+        };
+
+        self.font_names
+            .get(&self.text_font)
+            .map(String::as_str)
+            .or_else(|| font::standard_name(self.text_font))
+            .and_then(|name| self.fonts.get(name, size))
+            .unwrap_or((Font::EMBEDDED_SIZE, &self.embedded_font))
+    }
+
+    /// Draw the text with its baseline starting at the location, moving the pen to its end
+    fn text(&mut self, location: (i32, i32), text: &str) {
+        self.text_location = location;
+
+        let size = match self.text_size {
+            0 => DEFAULT_TEXT_SIZE,
+            size => size,
+        };
+        let (font_size, font) = self.font();
+        let scale = |ratio: f64| ((size as f64 * ratio / font_size as f64).round() as i32).max(1);
+        let (scale_x, scale_y) = (scale(self.text_ratio.0), scale(self.text_ratio.1));
+
+        let face = self.text_face;
+        let extra = (face & face::BOLD != 0) as i32 + (face & face::EXTEND != 0) as i32
+            - (face & face::CONDENSE != 0) as i32
+            + self.char_extra as i32;
+        let (start, baseline) = location;
+        let top = baseline - font.ascent() as i32 * scale_y;
+        let bottom = top + font.height() as i32 * scale_y;
+        // italic shears by one pixel every two rows above the baseline
+        let slant = |y: i32| match face & face::ITALIC {
+            0 => 0,
+            _ => (baseline - 1 - y).div_euclid(2),
+        };
+
+        let (codes, _, _) = WINDOWS_1252.encode(text);
+        let mut x = start;
+        let mut glyphs = Vec::new();
+        for &code in codes.iter() {
+            let Some(glyph) = font.glyph(code) else {
+                continue;
+            };
+
+            let left = x + glyph.offset as i32 * scale_x;
+            let right = left + glyph.width as i32 * scale_x;
+            let area = (left + slant(bottom), top, right + slant(top), bottom);
+            glyphs.push(Mask::from_fn(area, |px, py| {
+                glyph.contains(
+                    (px - left - slant(py)).div_euclid(scale_x),
+                    (py - top).div_euclid(scale_y),
+                )
+            }));
+
+            x += glyph.advance as i32 * scale_x + extra;
+            if code == b' ' {
+                x += (self.space_extra as f64 / 65536.).round() as i32;
+            }
+        }
+        self.pen_location = (x, baseline);
+
+        let Some(mut mask) = glyphs.into_iter().reduce(|acc, glyph| acc.union(&glyph)) else {
+            return;
+        };
+        if face & face::BOLD != 0 {
+            mask = mask.union(&mask.clone().offset(1, 0));
+        }
+        if face & (face::OUTLINE | face::SHADOW) != 0 {
+            let grown = [
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ]
+            .into_iter()
+            .fold(mask.clone(), |acc, (dx, dy)| {
+                acc.union(&mask.clone().offset(dx, dy))
+            });
+            let grown = match face & face::SHADOW {
+                0 => grown,
+                _ => grown.union(&grown.clone().offset(1, 1)),
+            };
+            mask = grown.difference(&mask);
+        }
+        if face & face::UNDERLINE != 0 {
+            let underline = Mask::from_fn((start, baseline + 1, x, baseline + 2), |_, _| true);
+            mask = mask.union(&underline);
+        }
+
+        // copy also erases the background of the glyphs, the other source modes draw them alone
+        let mode = match self.text_mode {
+            mode @ 0..=7 => mode,
+            _ => SRC_OR,
+        };
+        let (left, glyphs_top, right, glyphs_bottom) = mask.area();
+        let area = match mode & 0x03 {
+            0 => (
+                left.min(start),
+                glyphs_top.min(top),
+                right.max(x),
+                glyphs_bottom.max(bottom),
+            ),
+            _ => mask.area(),
+        };
+        let (fore, back) = (self.fore_color, self.back_color);
+        let (dx, dy) = self.origin;
+        self.paint(&Mask::from_fn(area, |_, _| true), |x, y, current| {
+            let ink = match mask.contains(x + dx, y + dy) {
+                true => Ink::Fore,
+                false if mode & 0x03 == 0 => Ink::Back,
+                false => return current,
+            };
+            transfer(mode, ink, fore, back, current)
+        });
+    }
+
+    /// Draw with the pen, moving it to the end
+    fn line(&mut self, from: (i32, i32), to: (i32, i32)) {
+        self.pen_location = to;
+
+        let mask = self.stroke(&[from, to]);
+        self.draw(Verb::Paint, &mask);
+    }
+
+    /// Pixels under the pen, its top left corner following the lines between the points
+    fn stroke(&self, points: &[(i32, i32)]) -> Mask {
+        if points.is_empty() {
+            return Mask::from_fn((0, 0, 0, 0), |_, _| false);
+        }
+
+        let (pen_width, pen_height) = self.pen_size;
+        let (mut left, mut top) = (i32::MAX, i32::MAX);
+        let (mut right, mut bottom) = (i32::MIN, i32::MIN);
+        for &(x, y) in points {
+            (left, top) = (left.min(x), top.min(y));
+            (right, bottom) = (right.max(x + pen_width), bottom.max(y + pen_height));
+        }
+
+        let bounds = intersect((left, top, right, bottom), self.area());
+        let width = (bounds.2 - bounds.0).max(0);
+        let height = (bounds.3 - bounds.1).max(0);
+        let mut bits = vec![false; (width * height) as usize];
+        let mut stamp = |x: i32, y: i32| {
+            for row in (y - bounds.1).max(0)..(y + pen_height - bounds.1).min(height) {
+                for column in (x - bounds.0).max(0)..(x + pen_width - bounds.0).min(width) {
+                    bits[(row * width + column) as usize] = true;
+                }
+            }
+        };
+
+        for segment in points.windows(2) {
+            let ((mut x, mut y), (end_x, end_y)) = (segment[0], segment[1]);
+            let (dx, dy) = ((end_x - x).abs(), -(end_y - y).abs());
+            let (step_x, step_y) = ((end_x - x).signum(), (end_y - y).signum());
+            let mut error = dx + dy;
+            loop {
+                stamp(x, y);
+                if (x, y) == (end_x, end_y) {
+                    break;
+                }
+                let doubled = 2 * error;
+                if doubled >= dy {
+                    error += dy;
+                    x += step_x;
+                }
+                if doubled <= dx {
+                    error += dx;
+                    y += step_y;
+                }
+            }
+        }
+
+        Mask::from_fn(bounds, |x, y| {
+            bits[((y - bounds.1) * width + (x - bounds.0)) as usize]
+        })
+    }
+
+    /// Border of the shape, as thick as the pen
+    fn outline(&self, mask: Mask) -> Mask {
+        let (width, height) = self.pen_size;
+
+        mask.difference(&mask.eroded(width, height))
+    }
+
+    fn shape(&mut self, verb: Verb, shape: &Shape) {
+        let area = self.area();
+        let mut rect = |rect: &Option<Rectangle>| {
+            if let Some(rect) = rect {
+                self.last_rect = *rect;
+            }
+            self.last_rect.area()
+        };
+
+        let mask = match shape {
+            Shape::Rect(r) => Mask::from_fn(intersect(rect(r), area), |_, _| true),
+            Shape::RRect(r) => {
+                let (bounds, oval_size) = (rect(r), self.oval_size);
+                Mask::from_fn(intersect(bounds, area), |x, y| {
+                    in_rounded_rect(bounds, oval_size, x, y)
+                })
+            }
+            Shape::Oval(r) => {
+                let bounds = rect(r);
+                let oval_size = (bounds.2 - bounds.0, bounds.3 - bounds.1);
+                Mask::from_fn(intersect(bounds, area), |x, y| {
+                    in_rounded_rect(bounds, oval_size, x, y)
+                })
+            }
+            Shape::Arc {
+                rect: r,
+                start,
+                angle,
+            } => {
+                let bounds = rect(r);
+                let oval_size = (bounds.2 - bounds.0, bounds.3 - bounds.1);
+                let oval = Mask::from_fn(intersect(bounds, area), |x, y| {
+                    in_rounded_rect(bounds, oval_size, x, y)
+                });
+                let wedge = Mask::from_fn(intersect(bounds, area), |x, y| {
+                    in_wedge(bounds, *start, *angle, x, y)
+                });
+
+                // only the curved part is framed
+                let oval = match verb {
+                    Verb::Frame => self.outline(oval),
+                    _ => oval,
+                };
+                self.draw(verb, &oval.intersection(&wedge));
+                return;
+            }
+            Shape::Poly(polygon) => {
+                if let Some(polygon) = polygon {
+                    self.last_polygon = Some(polygon.clone());
+                }
+                let Some(polygon) = &self.last_polygon else {
+                    return;
+                };
+
+                // framed by the pen along the points
+                let mask = match verb {
+                    Verb::Frame => self.stroke(
+                        &polygon
+                            .points
+                            .iter()
+                            .map(|p| (p.x as i32, p.y as i32))
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => Mask::from_fn(intersect(polygon.bounds.area(), area), |x, y| {
+                        in_polygon(&polygon.points, x, y)
+                    }),
+                };
+                self.draw(verb, &mask);
+                return;
+            }
+            Shape::Rgn(region) => {
+                if let Some(region) = region {
+                    self.last_region = Some(region.clone());
+                }
+                let Some(region) = &self.last_region else {
+                    return;
+                };
+                region.mask(area)
+            }
+        };
+
+        let mask = match verb {
+            Verb::Frame => self.outline(mask),
+            _ => mask,
+        };
+        self.draw(verb, &mask);
+    }
+
+    /// Draw the image scaled to the destination with the transfer mode, where the mask allows,
+    /// blending the result by the image's alpha
+    pub(crate) fn draw_image(
+        &mut self,
+        image: &Image,
+        destination: &Rectangle,
+        mode: u16,
+        mask: Option<&Mask>,
+    ) {
+        let (left, top, _, _) = destination.area();
+        let (width, height) = (destination.width(), destination.height());
+        if width == 0 || height == 0 || image.width() == 0 || image.height() == 0 {
+            return;
+        }
+
+        let visible = Mask::from_fn(intersect(destination.area(), self.area()), |x, y| {
+            mask.is_none_or(|mask| mask.contains(x, y))
+        });
+        let (dx, dy) = self.origin;
+        let colors = (self.fore_color, self.back_color, self.op_color);
+        self.paint(&visible, |x, y, current| {
+            let column = (x + dx - left) as usize * image.width() / width;
+            let row = (y + dy - top) as usize * image.height() / height;
+            let [r, g, b, alpha] = image.pixel(column, row);
+            let transferred = transfer_source(mode, [r, g, b], colors, current);
+
+            let mut ret = current;
+            for (channel, color) in ret.iter_mut().zip(transferred) {
+                *channel = ((color as u16 * alpha as u16 + *channel as u16 * (0xFF - alpha as u16))
+                    / 0xFF) as u8;
+            }
+            ret
+        });
+    }
+
+    /// Replace the color of the visible pixels of the mask, given in drawing coordinates
+    fn paint(&mut self, mask: &Mask, color_of: impl Fn(i32, i32, [u8; 3]) -> [u8; 3]) {
+        let (x, y) = self.origin;
+        let mut mask = mask.clone().offset(-x, -y);
+        if let Some(clip) = &self.clip {
+            mask = mask.intersection(clip);
+        }
+        self.draws += 1;
+
+        self.surface.apply(&mask, color_of);
+    }
+
+    /// Color the pixels of the mask, given in drawing coordinates, as the verb does
+    fn draw(&mut self, verb: Verb, mask: &Mask) {
+        let (fore, back) = (self.fore_color, self.back_color);
+        let (pattern, mode) = match verb {
+            Verb::Frame | Verb::Paint => (&self.pen_pattern, self.pen_mode),
+            Verb::Erase => (&self.back_pattern, PAT_COPY),
+            Verb::Fill => (&self.fill_pattern, PAT_COPY),
+            Verb::Invert => {
+                self.paint(mask, |_, _, current| current.map(|c| !c));
+                return;
+            }
+        };
+        let pattern = pattern.clone();
+
+        self.paint(mask, |x, y, current| {
+            transfer(mode, pattern.ink(x, y), fore, back, current)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{nfnt, rect, PictBuilder},
+        PICT,
+    };
+
+    use super::*;
+
+    fn draw(width: u16, height: u16, ops: &[(u16, Vec<u8>)]) -> Image {
+        let raw = ops
+            .iter()
+            .fold(
+                PictBuilder::new(width, height),
+                |builder, (opcode, content)| builder.op(*opcode, content),
+            )
+            .build();
+
+        PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to render")
+    }
+
+    /// Black pixels as `#`, white as `.` and others as `?`
+    fn rows(image: &Image) -> Vec<String> {
+        (0..image.height())
+            .map(|y| {
+                (0..image.width())
+                    .map(|x| match image.pixel(x, y) {
+                        [0, 0, 0, _] => '#',
+                        [0xFF, 0xFF, 0xFF, _] => '.',
+                        _ => '?',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn point(x: i16, y: i16) -> Vec<u8> {
+        [y, x].into_iter().flat_map(i16::to_be_bytes).collect()
+    }
+
+    #[test]
+    fn paint_and_frame_rect() {
+        let image = draw(
+            5,
+            4,
+            &[(0x0031, rect(1, 0, 3, 2)), (0x0030, rect(0, 2, 4, 5))],
+        );
+
+        assert_eq!(rows(&image), ["..###", "###.#", "###.#", "..###"]);
+    }
+
+    #[test]
+    fn line_with_pen_size() {
+        let image = draw(
+            4,
+            4,
+            &[
+                (0x0007, point(2, 1)),
+                (0x0020, [point(0, 0), point(2, 2)].concat()),
+            ],
+        );
+
+        assert_eq!(rows(&image), ["##..", ".##.", "..##", "...."]);
+    }
+
+    #[test]
+    fn shallow_line() {
+        let image = draw(3, 3, &[(0x0020, [point(0, 1), point(2, 2)].concat())]);
+
+        assert_eq!(rows(&image), ["...", "#..", ".##"]);
+    }
+
+    #[test]
+    fn oval_and_colors() {
+        let image = draw(
+            5,
+            5,
+            &[
+                (0x001A, vec![0xFF, 0xFF, 0, 0, 0, 0]),
+                (0x0051, rect(0, 0, 5, 5)),
+            ],
+        );
+
+        assert_eq!(image.pixel(2, 2), [0xFF, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(0, 2), [0xFF, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(0, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn same_rect_with_patterns() {
+        let image = draw(
+            4,
+            2,
+            &[
+                (0x0031, rect(0, 0, 2, 4)),
+                (0x0002, vec![0xAA, 0x55, 0, 0, 0, 0, 0, 0]),
+                (0x003A, vec![]),
+            ],
+        );
+
+        assert_eq!(rows(&image), ["#.#.", ".#.#"]);
+    }
+
+    #[test]
+    fn xor_mode() {
+        let image = draw(
+            3,
+            1,
+            &[
+                (0x0008, 10u16.to_be_bytes().to_vec()),
+                (0x0031, rect(0, 0, 1, 2)),
+                (0x0031, rect(0, 1, 1, 3)),
+            ],
+        );
+
+        assert_eq!(rows(&image), ["#.#"]);
+    }
+
+    #[test]
+    fn arc_quadrant() {
+        let mut arc = rect(0, 0, 4, 4);
+        arc.extend([0i16, 90].into_iter().flat_map(i16::to_be_bytes));
+
+        let image = draw(4, 4, &[(0x0061, arc)]);
+
+        assert_eq!(rows(&image), ["..#.", "..##", "....", "...."]);
+    }
+
+    #[test]
+    fn polygon_inside() {
+        let mut polygon = 22u16.to_be_bytes().to_vec();
+        polygon.extend(rect(0, 0, 4, 4));
+        for (x, y) in [(0, 0), (4, 4), (0, 4)] {
+            polygon.extend(point(x, y));
+        }
+
+        let image = draw(4, 4, &[(0x0071, polygon)]);
+
+        assert_eq!(rows(&image), ["....", "#...", "##..", "###."]);
+    }
+
+    #[test]
+    fn clipped() {
+        let mut clip = 10u16.to_be_bytes().to_vec();
+        clip.extend(rect(0, 0, 1, 2));
+
+        let image = draw(3, 2, &[(0x0001, clip), (0x0031, rect(0, 0, 2, 3))]);
+
+        assert_eq!(rows(&image), ["##.", "..."]);
+    }
+
+    fn text(x: i16, y: i16, text: &str) -> Vec<u8> {
+        let mut ret = point(x, y);
+        ret.push(text.len() as u8);
+        ret.extend_from_slice(text.as_bytes());
+        ret
+    }
+
+    #[test]
+    fn source_modes() {
+        let colors = ([0; 3], [0xFF; 3], [0x80; 3]);
+        let (gray, dark) = ([0xC0; 3], [0x40; 3]);
+
+        assert_eq!(transfer_source(0, gray, colors, dark), gray);
+        assert_eq!(transfer_source(1, gray, colors, dark), [0x40; 3]);
+        assert_eq!(transfer_source(3, [0; 3], colors, dark), [0xFF; 3]);
+        assert_eq!(transfer_source(4, gray, colors, dark), [0x3F; 3]);
+        assert_eq!(transfer_source(33, gray, colors, dark), [0x80; 3]);
+        assert_eq!(transfer_source(35, gray, colors, dark), [0x80; 3]);
+        assert_eq!(transfer_source(36, [0xFF; 3], colors, dark), dark);
+        assert_eq!(transfer_source(37, gray, colors, dark), gray);
+        assert_eq!(transfer_source(39, gray, colors, dark), dark);
+    }
+
+    #[test]
+    fn embedded_font() {
+        let image = draw(8, 8, &[(0x0028, text(0, 7, "I"))]);
+
+        assert_eq!(
+            rows(&image),
+            [
+                ".####...", "..##....", "..##....", "..##....", "..##....", "..##....", ".####...",
+                "........",
+            ]
+        );
+    }
+
+    #[test]
+    fn named_font_and_relative_text() {
+        let mut font_name = 7u16.to_be_bytes().to_vec();
+        font_name.extend_from_slice(&200i16.to_be_bytes());
+        font_name.push(4);
+        font_name.extend_from_slice(b"Tiny");
+
+        let raw = PictBuilder::new(7, 3)
+            .op(0x002C, &font_name)
+            .op(0x0003, &200i16.to_be_bytes())
+            .op(0x000D, &3i16.to_be_bytes())
+            .op(0x0028, &text(0, 2, "AB"))
+            .op(0x0029, &[5, 1, b'A'])
+            .build();
+        let mut fonts = Fonts::default();
+        fonts.insert(
+            "tiny",
+            3,
+            Font::parse_nfnt(nfnt().as_slice()).expect("to parse"),
+        );
+
+        let image = PICT::parse_with_fonts(raw.as_slice(), &fonts)
+            .and_then(|pict| pict.to_image())
+            .expect("to render");
+
+        assert_eq!(rows(&image), ["##.#.##", "##.#.##", "......."]);
+    }
+
+    #[test]
+    fn underlined_copy() {
+        let image = draw(
+            5,
+            3,
+            &[
+                (0x0031, rect(0, 0, 3, 5)),
+                (0x0004, vec![1 << 2]),
+                (0x0005, 0u16.to_be_bytes().to_vec()),
+                (0x0028, text(1, 1, " ")),
+            ],
+        );
+
+        assert_eq!(rows(&image), ["#....", "#....", "#####"]);
+    }
+}
@@ -0,0 +1,60 @@
+use bytes::Buf;
+
+use crate::{utils::ensure_remains_bytes, Result};
+
+/// Colors of indexed pixels
+pub struct ColorTable {
+    flags: u16,
+    /// Value and 16 bits components of each color
+    entries: Vec<(u16, [u16; 3])>,
+}
+
+impl ColorTable {
+    /// Entries are indexed by position rather than by value
+    const DEVICE_FLAG: u16 = 0x8000;
+
+    pub(crate) fn parse(mut buf: impl Buf) -> Result<Self> {
+        let (_seed, flags, count) = {
+            let mut header = ensure_remains_bytes(&mut buf, 8)?;
+            (
+                header.get_u32(),
+                header.get_u16(),
+                header.get_i16() as isize + 1,
+            )
+        };
+
+        let mut buf = ensure_remains_bytes(buf, count.max(0) as usize * 8)?;
+        let entries = (0..count)
+            .map(|_| {
+                let value = buf.get_u16();
+                (value, [buf.get_u16(), buf.get_u16(), buf.get_u16()])
+            })
+            .collect();
+
+        Ok(Self { flags, entries })
+    }
+
+    /// Colors by index, missing ones are black
+    pub fn palette(&self) -> Vec<[u8; 3]> {
+        let mut ret = vec![[0; 3]; 256];
+        for (position, (value, color)) in self.entries.iter().enumerate() {
+            let index = match self.flags & Self::DEVICE_FLAG {
+                0 => *value as usize,
+                _ => position,
+            };
+            if let Some(entry) = ret.get_mut(index) {
+                *entry = color.map(|c| (c >> 8) as u8);
+            }
+        }
+
+        ret
+    }
+
+    /// Human readable description of the fields, for debugging
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("color table flags", format!("{:04x}", self.flags)),
+            ("color table size", self.entries.len().to_string()),
+        ]
+    }
+}
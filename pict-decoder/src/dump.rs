@@ -0,0 +1,176 @@
+use bytes::Buf;
+
+use crate::{
+    operation::{Operation, Version},
+    Error, PICT,
+};
+
+/// Part of a picture, with its decoded fields
+pub struct Entry {
+    /// Position from the start of the picture
+    pub offset: usize,
+    /// Raw opcode, none for the preamble
+    pub opcode: Option<u16>,
+    pub name: String,
+    pub fields: Vec<(&'static str, String)>,
+    /// Drawn text or comment
+    pub text: Option<String>,
+}
+
+/// Structure of a picture, as far as it was parsed
+pub struct Dump {
+    pub entries: Vec<Entry>,
+    /// Position and reason of the parsing stop, if the end of picture wasn't reached
+    pub error: Option<(usize, Error)>,
+    /// Count of bytes after the end of picture
+    pub remaining: usize,
+}
+
+/// List every opcode of the picture, stopping at the first error
+///
+/// Contrary to [`PICT::parse`], the order of the opcodes isn't checked.
+pub fn dump(mut buf: impl Buf) -> Dump {
+    let total_size = buf.remaining();
+    let offset = |buf: &dyn Buf| total_size - buf.remaining();
+
+    let mut ret = Dump {
+        entries: Vec::new(),
+        error: None,
+        remaining: 0,
+    };
+
+    match PICT::parse_preamble(&mut buf) {
+        Ok((size, frame)) => ret.entries.push(Entry {
+            offset: offset(&buf) - 2 - 8,
+            opcode: None,
+            name: "Preamble".to_string(),
+            fields: vec![("size", size.to_string()), ("frame", frame.to_string())],
+            text: None,
+        }),
+        Err(e) => {
+            ret.error = Some((0, e));
+            return ret;
+        }
+    }
+
+    let version = match Version::detect(&buf) {
+        Ok(version) => version,
+        Err(e) => {
+            ret.error = Some((offset(&buf), e));
+            return ret;
+        }
+    };
+
+    while buf.has_remaining() {
+        let op_offset = offset(&buf);
+
+        let op = match Operation::parse(&mut buf, version) {
+            Ok(op) => op,
+            Err(e) => {
+                ret.error = Some((op_offset, e));
+                return ret;
+            }
+        };
+
+        let is_end = matches!(op, Operation::OpEndPic);
+        ret.entries.push(Entry {
+            offset: op_offset,
+            opcode: Some(op.opcode() as u16),
+            name: op.opcode().to_string(),
+            fields: op.fields(),
+            text: op.text().map(str::to_string),
+        });
+
+        if is_end {
+            ret.remaining = buf.remaining();
+            return ret;
+        }
+    }
+
+    ret.error = Some((offset(&buf), Error::UnexpectedEOB));
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::PictBuilder;
+
+    #[test]
+    fn list_opcodes() {
+        let raw = PictBuilder::new(1, 1)
+            .op(0x00A1, &[0, 100, 0, 2, b'h', b'i'])
+            .build();
+
+        let dump = dump(raw.as_slice());
+
+        assert!(dump.error.is_none());
+        assert_eq!(dump.remaining, 0);
+        assert_eq!(
+            dump.entries
+                .iter()
+                .map(|e| (e.offset, e.name.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (512, "Preamble"),
+                (522, "VersionOp"),
+                (524, "Version"),
+                (526, "HeaderOp"),
+                (552, "LongComment"),
+                (560, "OpEndPic"),
+            ]
+        );
+        assert_eq!(dump.entries[4].fields[1], ("text", "\"hi\"".to_string()));
+        assert_eq!(dump.entries[4].text.as_deref(), Some("hi"));
+        assert_eq!(dump.entries[5].text, None);
+    }
+
+    #[test]
+    fn describe_direct_bits() {
+        let raw = PictBuilder::new(2, 1)
+            .direct_bits_rect(2, 1, &[0, 1, 2, 3, 0, 4, 5, 6])
+            .build();
+
+        let dump = dump(raw.as_slice());
+
+        assert!(dump.error.is_none());
+        let fields = &dump.entries[4].fields;
+        assert!(fields.contains(&("pack type", "1".to_string())));
+        assert!(fields.contains(&("destination", "(0, 0)-(1, 2)".to_string())));
+    }
+
+    #[test]
+    fn byte_opcodes_of_version_1() {
+        let raw = crate::tests::version_1(1, 1, &[0x04, 0x01, 0xA0, 0, 4]);
+
+        let dump = dump(raw.as_slice());
+
+        assert!(dump.error.is_none());
+        assert_eq!(
+            dump.entries
+                .iter()
+                .map(|e| (e.offset, e.name.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (512, "Preamble"),
+                (522, "VersionOp"),
+                (524, "TxFace"),
+                (526, "ShortComment"),
+                (529, "OpEndPic"),
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_at_unsupported_opcode() {
+        let raw = PictBuilder::new(1, 1).op(0x0123, &[]).build();
+
+        let dump = dump(raw.as_slice());
+
+        assert_eq!(dump.entries.len(), 4);
+        assert!(matches!(
+            dump.error,
+            Some((552, Error::UnsupportedOpcode(0x0123)))
+        ));
+    }
+}
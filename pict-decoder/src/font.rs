@@ -0,0 +1,270 @@
+// https://developer.apple.com/library/archive/documentation/mac/Text/Text-250.html
+
+use std::collections::HashMap;
+
+use bytes::Buf;
+use encoding_rs::MACINTOSH;
+use font8x8::{UnicodeFonts, BASIC_FONTS, LATIN_FONTS};
+
+use crate::{utils::ensure_remains_bytes, Error, Result};
+
+/// Names of the fonts numbered by the system, when the picture doesn't name them
+pub(crate) fn standard_name(id: i16) -> Option<&'static str> {
+    Some(match id {
+        0 => "Chicago",
+        1 | 3 => "Geneva",
+        2 => "New York",
+        4 => "Monaco",
+        5 => "Venice",
+        6 => "London",
+        7 => "Athens",
+        8 => "San Francisco",
+        9 => "Toronto",
+        11 => "Cairo",
+        12 => "Los Angeles",
+        20 => "Times",
+        21 => "Helvetica",
+        22 => "Courier",
+        23 => "Symbol",
+        _ => return None,
+    })
+}
+
+/// Image of a character, drawn from the pen's location
+#[derive(Clone, Debug)]
+pub(crate) struct Glyph {
+    /// Horizontal distance from the pen to the image
+    pub(crate) offset: i16,
+    /// Horizontal distance the pen moves
+    pub(crate) advance: i16,
+    pub(crate) width: usize,
+    /// Rows of the font's height, from its ascent
+    bits: Vec<bool>,
+}
+
+impl Glyph {
+    /// Whether the pixel is lit, from the top left of the image
+    pub(crate) fn contains(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.width {
+            return false;
+        }
+
+        self.bits
+            .get(y as usize * self.width + x as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Bitmap font of a single size, indexed by Mac OS Roman codes
+#[derive(Clone, Debug)]
+pub struct Font {
+    ascent: i16,
+    descent: i16,
+    glyphs: HashMap<u8, Glyph>,
+    /// Drawn for the characters without a glyph
+    missing: Option<Glyph>,
+}
+
+impl Font {
+    /// Size of the embedded font
+    pub(crate) const EMBEDDED_SIZE: u16 = 12;
+
+    /// 8x8 pixels font, used when no other is available
+    pub(crate) fn embedded() -> Self {
+        let glyph = |rows: [u8; 8]| Glyph {
+            offset: 0,
+            advance: 8,
+            width: 8,
+            bits: rows
+                .into_iter()
+                .flat_map(|row| (0..8).map(move |x| row & (1 << x) != 0))
+                .collect(),
+        };
+
+        let glyphs = (0x20..=0xFF)
+            .filter_map(|code: u8| {
+                let c = MACINTOSH
+                    .decode_without_bom_handling(&[code])
+                    .0
+                    .chars()
+                    .next()?;
+                let rows = BASIC_FONTS.get(c).or_else(|| LATIN_FONTS.get(c))?;
+                Some((code, glyph(rows)))
+            })
+            .collect();
+
+        Self {
+            ascent: 7,
+            descent: 1,
+            glyphs,
+            missing: BASIC_FONTS.get('?').map(glyph),
+        }
+    }
+
+    /// Parse a NFNT or FONT resource, only of black and white glyphs
+    pub fn parse_nfnt(mut buf: impl Buf) -> Result<Self> {
+        const HEADER_SIZE: usize = 26;
+        /// Position of the offset to the widths table, which is counted in words from there
+        const WIDTHS_OFFSET_POSITION: usize = 16;
+
+        let (font_type, first_char, last_char) = {
+            let mut header = ensure_remains_bytes(&mut buf, 6)?;
+            (header.get_u16(), header.get_i16(), header.get_i16())
+        };
+        if font_type & 0x000C != 0 {
+            return Err(Error::UnsupportedFontType(font_type));
+        }
+        if !(0..=0xFF).contains(&first_char) || !(first_char..=0xFF).contains(&last_char) {
+            return Err(Error::InvalidFontCharRange(first_char, last_char));
+        }
+        let (kern_max, rect_height, widths_offset, ascent, descent, row_bytes) = {
+            let mut header = ensure_remains_bytes(&mut buf, HEADER_SIZE - 6)?;
+            let _max_width = header.get_i16();
+            let kern_max = header.get_i16();
+            let _negated_descent = header.get_i16();
+            let _rect_width = header.get_i16();
+            let rect_height = header.get_i16().max(0) as usize;
+            let widths_offset = header.get_u16() as usize * 2;
+            let ascent = header.get_i16();
+            let descent = header.get_i16();
+            let _leading = header.get_i16();
+            let row_bytes = header.get_i16().max(0) as usize * 2;
+            (
+                kern_max,
+                rect_height,
+                widths_offset,
+                ascent,
+                descent,
+                row_bytes,
+            )
+        };
+
+        let image_size = row_bytes * rect_height;
+        let mut image = vec![0u8; image_size];
+        ensure_remains_bytes(&mut buf, image_size)?.copy_to_slice(&mut image);
+
+        // every character, the missing glyph then the end of the image
+        let entries = (last_char - first_char) as usize + 3;
+        let locations: Vec<_> = {
+            let mut table = ensure_remains_bytes(&mut buf, entries * 2)?;
+            (0..entries).map(|_| table.get_u16() as usize).collect()
+        };
+
+        let read = HEADER_SIZE + image_size + entries * 2;
+        let widths_position = WIDTHS_OFFSET_POSITION + widths_offset;
+        if widths_position < read {
+            return Err(Error::InvalidFontWidthsOffset(widths_offset));
+        }
+        ensure_remains_bytes(&mut buf, widths_position - read)?.advance(widths_position - read);
+        let mut widths = ensure_remains_bytes(buf, (entries - 1) * 2)?;
+
+        let mut glyphs = HashMap::new();
+        let mut missing = None;
+        for index in 0..entries - 1 {
+            let offset_and_width = widths.get_u16();
+            if offset_and_width == 0xFFFF {
+                continue;
+            }
+
+            let (start, end) = (locations[index], locations[index + 1].max(locations[index]));
+            let width = end - start;
+            let bits = (0..rect_height)
+                .flat_map(|y| (start..end).map(move |x| (y, x)))
+                .map(|(y, x)| {
+                    let byte = image.get(y * row_bytes + x / 8).copied().unwrap_or(0);
+                    byte & (0x80 >> (x % 8)) != 0
+                })
+                .collect();
+
+            let glyph = Glyph {
+                offset: kern_max + (offset_and_width >> 8) as i16,
+                advance: (offset_and_width & 0xFF) as i16,
+                width,
+                bits,
+            };
+            match first_char as usize + index {
+                code if code <= last_char as usize => {
+                    glyphs.insert(code as u8, glyph);
+                }
+                _ => missing = Some(glyph),
+            }
+        }
+
+        Ok(Self {
+            ascent,
+            descent,
+            glyphs,
+            missing,
+        })
+    }
+
+    pub(crate) fn ascent(&self) -> i16 {
+        self.ascent
+    }
+
+    /// Ascent and descent, the rows of every glyph
+    pub(crate) fn height(&self) -> usize {
+        (self.ascent as i32 + self.descent as i32).max(0) as usize
+    }
+
+    /// Glyph of the code or the missing one
+    pub(crate) fn glyph(&self, code: u8) -> Option<&Glyph> {
+        self.glyphs.get(&code).or(self.missing.as_ref())
+    }
+}
+
+/// Fonts available to draw text, by family name and size
+#[derive(Clone, Debug, Default)]
+pub struct Fonts {
+    families: HashMap<String, Vec<(u16, Font)>>,
+}
+
+impl Fonts {
+    /// Add the font of the given family and size, as named by FOND resources
+    pub fn insert(&mut self, family: &str, size: u16, font: Font) {
+        let sizes = self.families.entry(family.to_lowercase()).or_default();
+        sizes.retain(|(existing, _)| *existing != size);
+        sizes.push((size, font));
+    }
+
+    /// Font of the family with the nearest size, and that size
+    pub(crate) fn get(&self, family: &str, size: u16) -> Option<(u16, &Font)> {
+        self.families
+            .get(&family.to_lowercase())?
+            .iter()
+            .min_by_key(|(existing, _)| existing.abs_diff(size))
+            .map(|(existing, font)| (*existing, font))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::nfnt;
+
+    #[test]
+    fn parse_glyphs() {
+        let font = Font::parse_nfnt(nfnt().as_slice()).expect("to parse");
+
+        assert_eq!((font.ascent(), font.height()), (2, 3));
+
+        let glyph = font.glyph(b'A').expect("a glyph");
+        assert_eq!((glyph.offset, glyph.advance, glyph.width), (0, 3, 2));
+        assert!(glyph.contains(0, 0) && glyph.contains(1, 1) && !glyph.contains(0, 2));
+
+        let missing = font.glyph(b'B').expect("the missing glyph");
+        assert_eq!((missing.advance, missing.width), (2, 1));
+    }
+
+    #[test]
+    fn nearest_size() {
+        let mut fonts = Fonts::default();
+        fonts.insert("Geneva", 9, Font::embedded());
+        fonts.insert("Geneva", 24, Font::embedded());
+
+        assert_eq!(fonts.get("geneva", 12).map(|(size, _)| size), Some(9));
+        assert_eq!(fonts.get("Geneva", 18).map(|(size, _)| size), Some(24));
+        assert!(fonts.get("Chicago", 12).is_none());
+    }
+}
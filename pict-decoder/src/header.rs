@@ -0,0 +1,109 @@
+use std::iter;
+
+use bytes::Buf;
+
+use crate::{
+    operation::{expect_op, Opcode, Operation, Version},
+    rectangle::Rectangle,
+    Error, Result, PICT,
+};
+
+/// Frame and resolution of a picture, whichever header flavour it uses
+#[derive(Clone, Debug, PartialEq)]
+pub struct PictureHeader {
+    /// Version of the opcodes, 1 or 2
+    pub version: u8,
+    /// Bounds of the drawing, in its own coordinates
+    pub frame: Rectangle,
+    /// Dots per inch
+    pub horizontal_resolution: f64,
+    /// Dots per inch
+    pub vertical_resolution: f64,
+}
+
+impl PictureHeader {
+    /// Resolution of version 1 and original version 2 pictures
+    const SCREEN_RESOLUTION: f64 = 72.0;
+
+    /// Parse from the start of a picture up to its first drawing opcode
+    pub fn parse(mut buf: impl Buf) -> Result<Self> {
+        let (_size, frame) = PICT::parse_preamble(&mut buf)?;
+        let version = Version::detect(&buf)?;
+
+        let mut opcodes = iter::from_fn(|| {
+            buf.has_remaining()
+                .then(|| Operation::parse(&mut buf, version))
+        });
+
+        expect_op(&mut opcodes, Opcode::VersionOp)?;
+        if version == Version::V1 {
+            return Ok(Self {
+                version: 1,
+                frame,
+                horizontal_resolution: Self::SCREEN_RESOLUTION,
+                vertical_resolution: Self::SCREEN_RESOLUTION,
+            });
+        }
+
+        expect_op(&mut opcodes, Opcode::Version)?;
+        match expect_op(&mut opcodes, Opcode::HeaderOp)? {
+            Operation::HeaderOp {
+                resolution: (horizontal, vertical),
+                source,
+                ..
+            } => Ok(Self {
+                version: 2,
+                frame: source,
+                horizontal_resolution: horizontal as f64 / 65536.0,
+                vertical_resolution: vertical as f64 / 65536.0,
+            }),
+            op => Err(Error::UnexpectedOpcode(op.opcode() as u16)),
+        }
+    }
+
+    /// Version of the opcodes following the header
+    pub(crate) fn opcodes_version(&self) -> Version {
+        match self.version {
+            1 => Version::V1,
+            _ => Version::V2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PictureHeader;
+    use crate::tests::{version_1, PictBuilder};
+
+    #[test]
+    fn extended_version_2() {
+        let raw = PictBuilder::new(4, 3).build();
+
+        let header = PictureHeader::parse(raw.as_slice()).expect("to parse");
+
+        assert_eq!(header.version, 2);
+        assert_eq!((header.frame.width(), header.frame.height()), (4, 3));
+        assert_eq!(header.horizontal_resolution, 72.0);
+    }
+
+    #[test]
+    fn original_version_2() {
+        let raw = PictBuilder::original(4, 3).build();
+
+        let header = PictureHeader::parse(raw.as_slice()).expect("to parse");
+
+        assert_eq!(header.version, 2);
+        assert_eq!((header.frame.width(), header.frame.height()), (4, 3));
+        assert_eq!(header.vertical_resolution, 72.0);
+    }
+
+    #[test]
+    fn version_1_frame() {
+        let raw = version_1(5, 2, &[]);
+
+        let header = PictureHeader::parse(raw.as_slice()).expect("to parse");
+
+        assert_eq!(header.version, 1);
+        assert_eq!((header.frame.width(), header.frame.height()), (5, 2));
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{Error, Result};
+
+/// Layout of a pixel in an [`Image`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    Gray8,
+    /// Index in the palette, out of range indexes are black
+    Indexed8 {
+        palette: Vec<[u8; 3]>,
+    },
+    /// Big endian, the top bit is unused
+    RGB555,
+    RGB24,
+    RGBA32,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::Gray8 | Self::Indexed8 { .. } => 1,
+            Self::RGB555 => 2,
+            Self::RGB24 => 3,
+            Self::RGBA32 => 4,
+        }
+    }
+
+    fn read(&self, raw: &[u8]) -> [u8; 4] {
+        match self {
+            Self::Gray8 => [raw[0], raw[0], raw[0], 0xFF],
+            Self::Indexed8 { palette } => {
+                let [r, g, b] = palette.get(raw[0] as usize).copied().unwrap_or([0; 3]);
+                [r, g, b, 0xFF]
+            }
+            Self::RGB555 => {
+                let value = u16::from_be_bytes([raw[0], raw[1]]);
+                let expand = |shift: u16| {
+                    let five_bits = ((value >> shift) & 0x1F) as u8;
+                    (five_bits << 3) | (five_bits >> 2)
+                };
+                [expand(10), expand(5), expand(0), 0xFF]
+            }
+            Self::RGB24 => [raw[0], raw[1], raw[2], 0xFF],
+            Self::RGBA32 => [raw[0], raw[1], raw[2], raw[3]],
+        }
+    }
+
+    fn write(&self, [r, g, b, a]: [u8; 4], out: &mut Vec<u8>) {
+        match self {
+            Self::Gray8 => {
+                let luma = (299 * r as u32 + 587 * g as u32 + 114 * b as u32 + 500) / 1000;
+                out.push(luma as u8)
+            }
+            Self::Indexed8 { palette } => {
+                let distance = |[pr, pg, pb]: &[u8; 3]| {
+                    [(pr, r), (pg, g), (pb, b)]
+                        .into_iter()
+                        .map(|(&p, c)| (p as i32 - c as i32).pow(2))
+                        .sum::<i32>()
+                };
+                let nearest = (0..palette.len())
+                    .min_by_key(|&i| distance(&palette[i]))
+                    .unwrap_or(0);
+                out.push(nearest as u8)
+            }
+            Self::RGB555 => {
+                let value = ((r as u16 >> 3) << 10) | ((g as u16 >> 3) << 5) | (b as u16 >> 3);
+                out.extend_from_slice(&value.to_be_bytes())
+            }
+            Self::RGB24 => out.extend_from_slice(&[r, g, b]),
+            Self::RGBA32 => out.extend_from_slice(&[r, g, b, a]),
+        }
+    }
+}
+
+/// Decoded pixels, row by row from the top
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    stride: usize,
+    format: PixelFormat,
+    data: Vec<u8>,
+}
+
+impl Image {
+    /// Tightly packed pixels, without any padding between rows
+    pub fn new(width: usize, height: usize, format: PixelFormat, data: Vec<u8>) -> Result<Self> {
+        let stride = width * format.bytes_per_pixel();
+
+        Self::with_stride(width, height, stride, format, data)
+    }
+
+    /// Pixels with rows starting every `stride` bytes
+    pub fn with_stride(
+        width: usize,
+        height: usize,
+        stride: usize,
+        format: PixelFormat,
+        data: Vec<u8>,
+    ) -> Result<Self> {
+        let row_size = width * format.bytes_per_pixel();
+        let expected_size = match height {
+            0 => 0,
+            _ => stride * (height - 1) + row_size,
+        };
+        if stride < row_size || data.len() < expected_size {
+            return Err(Error::UnexpectedImageSize(data.len()));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            stride,
+            format,
+            data,
+        })
+    }
+
+    /// Rows of 1, 2, 4 or 8 bits indexes in the palette, leftmost pixels in the high bits
+    pub(crate) fn from_indexes(
+        width: usize,
+        height: usize,
+        stride: usize,
+        bits: u16,
+        palette: Vec<[u8; 3]>,
+        data: Vec<u8>,
+    ) -> Result<Self> {
+        let format = PixelFormat::Indexed8 { palette };
+        match bits {
+            8 => return Self::with_stride(width, height, stride, format, data),
+            1 | 2 | 4 => {}
+            _ => return Err(Error::UnsupportedPixelSize(bits)),
+        }
+
+        let row_size = (width * bits as usize).div_ceil(8);
+        if stride < row_size || data.len() < stride * height {
+            return Err(Error::UnexpectedImageSize(data.len()));
+        }
+
+        let per_byte = 8 / bits as usize;
+        let mask = (1u8 << bits) - 1;
+        let indexes = data
+            .chunks(stride)
+            .take(height)
+            .flat_map(|row| {
+                (0..width).map(move |x| {
+                    let shift = (per_byte - 1 - x % per_byte) * bits as usize;
+                    (row[x / per_byte] >> shift) & mask
+                })
+            })
+            .collect();
+
+        Self::new(width, height, format, indexes)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Count of bytes from the start of a row to the next
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn format(&self) -> &PixelFormat {
+        &self.format
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Pixels of the given row, without padding
+    pub fn row(&self, y: usize) -> &[u8] {
+        let start = y * self.stride;
+
+        &self.data[start..start + self.width * self.format.bytes_per_pixel()]
+    }
+
+    /// Color of the pixel as RGBA
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let size = self.format.bytes_per_pixel();
+
+        self.format.read(&self.row(y)[x * size..])
+    }
+
+    /// Copy of the given area, which is expected to be inside the image
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        let size = self.format.bytes_per_pixel();
+
+        let mut data = Vec::with_capacity(width * height * size);
+        for row in y..y + height {
+            data.extend_from_slice(&self.row(row)[x * size..(x + width) * size]);
+        }
+
+        Self {
+            width,
+            height,
+            stride: width * size,
+            format: self.format.clone(),
+            data,
+        }
+    }
+
+    /// Copy with the hidden pixels painted with the opaque background, as RGB or RGBA if it has alpha
+    pub(crate) fn masked(
+        &self,
+        is_visible: impl Fn(usize, usize) -> bool,
+        [r, g, b]: [u8; 3],
+    ) -> Self {
+        let format = match self.format {
+            PixelFormat::RGBA32 => PixelFormat::RGBA32,
+            _ => PixelFormat::RGB24,
+        };
+        let size = format.bytes_per_pixel();
+        let mut ret = self.convert(format);
+        for y in 0..ret.height {
+            for x in (0..ret.width).filter(|&x| !is_visible(x, y)) {
+                let offset = y * ret.stride + x * size;
+                ret.data[offset..offset + size].copy_from_slice(&[r, g, b, 0xFF][..size]);
+            }
+        }
+
+        ret
+    }
+
+    /// Tightly packed copy in the given format, colors are mapped to the nearest of a palette
+    pub fn convert(&self, format: PixelFormat) -> Self {
+        let mut data = Vec::with_capacity(self.width * self.height * format.bytes_per_pixel());
+        for y in 0..self.height {
+            for raw in self.row(y).chunks_exact(self.format.bytes_per_pixel()) {
+                format.write(self.format.read(raw), &mut data);
+            }
+        }
+
+        Self {
+            width: self.width,
+            height: self.height,
+            stride: self.width * format.bytes_per_pixel(),
+            format,
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_short_data() {
+        assert!(matches!(
+            Image::new(2, 2, PixelFormat::RGB24, vec![0; 11]),
+            Err(Error::UnexpectedImageSize(11))
+        ));
+        assert!(Image::with_stride(1, 2, 4, PixelFormat::RGB24, vec![0; 7]).is_ok());
+    }
+
+    #[test]
+    fn convert_with_stride() {
+        let image =
+            Image::with_stride(2, 2, 5, PixelFormat::Gray8, vec![10, 20, 0, 0, 0, 30, 40]).unwrap();
+
+        let converted = image.convert(PixelFormat::RGB24);
+
+        assert_eq!(converted.stride(), 6);
+        assert_eq!(converted.row(1), [30, 30, 30, 40, 40, 40]);
+    }
+
+    #[test]
+    fn convert_rgb555() {
+        let image = Image::new(2, 1, PixelFormat::RGB555, vec![0x7C, 0x00, 0x03, 0xFF]).unwrap();
+
+        assert_eq!(image.pixel(0, 0), [0xFF, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(1, 0), [0, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(
+            image
+                .convert(PixelFormat::RGBA32)
+                .convert(PixelFormat::RGB555),
+            image
+        );
+    }
+
+    #[test]
+    fn unpack_indexes() {
+        let palette = vec![[0; 3], [1; 3], [2; 3], [3; 3]];
+
+        let image = Image::from_indexes(
+            5,
+            2,
+            2,
+            2,
+            palette,
+            vec![0b00_01_10_11, 0b01_000000, 0xFF, 0xFF],
+        )
+        .unwrap();
+
+        assert_eq!(image.data(), [0, 1, 2, 3, 1, 3, 3, 3, 3, 3]);
+        assert!(matches!(
+            Image::from_indexes(1, 1, 1, 3, vec![], vec![0]),
+            Err(Error::UnsupportedPixelSize(3))
+        ));
+    }
+
+    #[test]
+    fn convert_to_palette() {
+        let palette = vec![[0, 0, 0], [255, 255, 255], [255, 0, 0]];
+        let image = Image::new(
+            3,
+            1,
+            PixelFormat::RGB24,
+            vec![250, 10, 5, 200, 200, 200, 1, 2, 3],
+        )
+        .unwrap();
+
+        let indexed = image.convert(PixelFormat::Indexed8 { palette });
+
+        assert_eq!(indexed.data(), [2, 1, 0]);
+        assert_eq!(indexed.pixel(0, 0), [255, 0, 0, 255]);
+    }
+}
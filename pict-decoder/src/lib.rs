@@ -1,14 +1,36 @@
 use std::{io, string};
 
+mod canvas;
+mod color_table;
+mod dump;
+mod font;
+mod header;
+mod image;
 mod operation;
+mod pattern;
 mod pict;
 mod pixmap;
 mod point;
 mod quicktime;
 mod rectangle;
+mod region;
+mod shape;
 mod utils;
 
+pub use color_table::ColorTable;
+pub use dump::{dump, Dump, Entry};
+pub use font::{Font, Fonts};
+pub use header::PictureHeader;
+pub use image::{Image, PixelFormat};
+pub use operation::{Opcode, Operation, Operations};
+pub use pattern::Pattern;
 pub use pict::PICT;
+pub use pixmap::PixMap;
+pub use point::Point;
+pub use quicktime::{ImageDescription, Matrix};
+pub use rectangle::Rectangle;
+pub use region::Region;
+pub use shape::{Polygon, Shape, Verb};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -35,6 +57,60 @@ pub enum Error {
 
     #[error("unsupported opcode {0:04x}")]
     UnsupportedOpcode(u16),
+    #[error("invalid opcode size: {0}")]
+    InvalidOpcodeSize(u32),
+    #[error("unsupported base address: {0:08x}")]
+    UnsupportedBaseAddress(u32),
+    #[error("invalid row bytes: {0:04x}")]
+    InvalidRowBytes(u16),
+    #[error("unsupported pixmap version: {0}")]
+    UnsupportedPixMapVersion(u16),
+    #[error("unsupported pack type: {0}")]
+    UnsupportedPackType(u16),
+    #[error("pack size should be zero for unpacked pixmap but is {0}")]
+    UnexpectedPackSize(u32),
+    #[error("invalid pixel type: {0}")]
+    InvalidPixelType(u16),
+    #[error("unsupported pixel size: {0}")]
+    UnsupportedPixelSize(u16),
+    #[error("unsupported {0} components of {1} bits")]
+    UnsupportedComponents(u16, u16),
+    #[error("unpack bits: {0}")]
+    PackBits(#[from] packbits::Error),
+    #[error("color planes are of different sizes")]
+    UnevenPlanes,
+    #[error("unsupported matte")]
+    UnsupportedMatte,
+    #[error("invalid region size: {0}")]
+    InvalidRegionSize(u16),
+    #[error("invalid polygon size: {0}")]
+    InvalidPolygonSize(u16),
+    #[error("unsupported font type: {0:04x}")]
+    UnsupportedFontType(u16),
+    #[error("invalid font characters range: {0} to {1}")]
+    InvalidFontCharRange(i16, i16),
+    #[error("invalid font widths offset: {0}")]
+    InvalidFontWidthsOffset(usize),
+    #[error("unexpected image description size: {0}")]
+    UnexpectedImageDescriptionSize(u32),
+
+    #[error("image data of {0} bytes doesn't match its dimensions")]
+    UnexpectedImageSize(usize),
+    #[error("JPEG decoding is disabled, enable the `jpeg` feature")]
+    UnsupportedJPEG,
+    #[cfg(feature = "jpeg")]
+    #[error("decode jpeg: {0}")]
+    JPEG(#[from] jpeg_decoder::Error),
+    #[error("unsupported JPEG pixel format: {0}")]
+    UnsupportedJPEGPixelFormat(String),
+    #[error("JPEG of {0}x{1} is smaller than its description")]
+    UnexpectedJPEGSize(u16, u16),
+    #[error("unsupported codec: {}", String::from_utf8_lossy(.0))]
+    UnsupportedCodec([u8; 4]),
+    #[error("unsupported depth {} for codec {}", .1, String::from_utf8_lossy(.0))]
+    UnsupportedCodecDepth([u8; 4], u16),
+    #[error("corrupted data for codec {}", String::from_utf8_lossy(.0))]
+    CorruptedCodecData([u8; 4]),
 
     #[error("end of picture found but buffer is not empty")]
     DataRemaining,
@@ -43,3 +119,6 @@ pub enum Error {
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests;
@@ -1,33 +1,133 @@
 use std::{cmp, fmt, vec};
 
 use bytes::Buf;
-use encoding_rs::WINDOWS_1252;
+use encoding_rs::{MACINTOSH, WINDOWS_1252};
 use strum::FromRepr;
 use tracing::warn;
 
 use crate::{
+    color_table::ColorTable,
+    pattern::Pattern,
     pixmap::PixMap,
     point::Point,
     quicktime::{ImageDescription, Matrix},
     rectangle::Rectangle,
-    utils::{skip_filler, skip_reserved},
+    region::Region,
+    shape::{Shape, Verb},
+    utils::{ensure_remains_bytes, skip_filler, skip_reserved},
     Error, Result,
 };
 
-#[derive(PartialEq, Eq, Debug, FromRepr, strum::Display)]
+/// Raw opcode of an operation, as of version 2 pictures
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, FromRepr, strum::Display)]
 #[repr(u16)]
-pub(crate) enum Opcode {
+#[non_exhaustive]
+pub enum Opcode {
     Nop = 0x0000,
     Clip = 0x0001,
+    BkPat = 0x0002,
     TxFont = 0x0003,
     TxFace = 0x0004,
+    TxMode = 0x0005,
+    SpExtra = 0x0006,
     PnSize = 0x0007,
+    PnMode = 0x0008,
+    PnPat = 0x0009,
+    FillPat = 0x000A,
+    OvSize = 0x000B,
+    Origin = 0x000C,
     TxSize = 0x000D,
+    FgColor = 0x000E,
+    BkColor = 0x000F,
     TxRatio = 0x0010,
     VersionOp = 0x0011,
+    BkPixPat = 0x0012,
+    PnPixPat = 0x0013,
+    FillPixPat = 0x0014,
+    PnLocHFrac = 0x0015,
+    ChExtra = 0x0016,
+    RGBFgCol = 0x001A,
+    RGBBkCol = 0x001B,
+    HiliteMode = 0x001C,
+    HiliteColor = 0x001D,
     DefHilite = 0x001E,
+    OpColor = 0x001F,
+    Line = 0x0020,
+    LineFrom = 0x0021,
+    ShortLine = 0x0022,
+    ShortLineFrom = 0x0023,
     LongText = 0x0028,
+    DHText = 0x0029,
+    DVText = 0x002A,
+    DHDVText = 0x002B,
+    FontName = 0x002C,
+    FrameRect = 0x0030,
+    PaintRect = 0x0031,
+    EraseRect = 0x0032,
+    InvertRect = 0x0033,
+    FillRect = 0x0034,
+    FrameSameRect = 0x0038,
+    PaintSameRect = 0x0039,
+    EraseSameRect = 0x003A,
+    InvertSameRect = 0x003B,
+    FillSameRect = 0x003C,
+    FrameRRect = 0x0040,
+    PaintRRect = 0x0041,
+    EraseRRect = 0x0042,
+    InvertRRect = 0x0043,
+    FillRRect = 0x0044,
+    FrameSameRRect = 0x0048,
+    PaintSameRRect = 0x0049,
+    EraseSameRRect = 0x004A,
+    InvertSameRRect = 0x004B,
+    FillSameRRect = 0x004C,
+    FrameOval = 0x0050,
+    PaintOval = 0x0051,
+    EraseOval = 0x0052,
+    InvertOval = 0x0053,
+    FillOval = 0x0054,
+    FrameSameOval = 0x0058,
+    PaintSameOval = 0x0059,
+    EraseSameOval = 0x005A,
+    InvertSameOval = 0x005B,
+    FillSameOval = 0x005C,
+    FrameArc = 0x0060,
+    PaintArc = 0x0061,
+    EraseArc = 0x0062,
+    InvertArc = 0x0063,
+    FillArc = 0x0064,
+    FrameSameArc = 0x0068,
+    PaintSameArc = 0x0069,
+    EraseSameArc = 0x006A,
+    InvertSameArc = 0x006B,
+    FillSameArc = 0x006C,
+    FramePoly = 0x0070,
+    PaintPoly = 0x0071,
+    ErasePoly = 0x0072,
+    InvertPoly = 0x0073,
+    FillPoly = 0x0074,
+    FrameSamePoly = 0x0078,
+    PaintSamePoly = 0x0079,
+    EraseSamePoly = 0x007A,
+    InvertSamePoly = 0x007B,
+    FillSamePoly = 0x007C,
+    FrameRgn = 0x0080,
+    PaintRgn = 0x0081,
+    EraseRgn = 0x0082,
+    InvertRgn = 0x0083,
+    FillRgn = 0x0084,
+    FrameSameRgn = 0x0088,
+    PaintSameRgn = 0x0089,
+    EraseSameRgn = 0x008A,
+    InvertSameRgn = 0x008B,
+    FillSameRgn = 0x008C,
+    BitsRect = 0x0090,
+    BitsRgn = 0x0091,
+    PackBitsRect = 0x0098,
+    PackBitsRgn = 0x0099,
     DirectBitsRect = 0x009A,
+    DirectBitsRgn = 0x009B,
+    ShortComment = 0x00A0,
     LongComment = 0x00A1,
     OpEndPic = 0x00FF,
     Version = 0x02FF,
@@ -35,34 +135,428 @@ pub(crate) enum Opcode {
     CompressedQuickTime = 0x8200,
 }
 
-#[allow(dead_code)]
-pub(crate) enum Operation {
+/// Format of the opcodes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Version {
+    /// Byte opcodes, without alignment
+    V1,
+    /// Word opcodes, with their data aligned on words
+    V2,
+}
+
+impl Version {
+    /// Recognize the opcodes starting the picture, without consuming them
+    pub(crate) fn detect(buf: &impl Buf) -> Result<Self> {
+        match buf.chunk() {
+            [0x11, 0x01, ..] => Ok(Self::V1),
+            [0x00, 0x11, ..] => Ok(Self::V2),
+            [0x11, version, ..] => Err(Error::UnsupportedVersion(*version)),
+            [] | [_] => Err(Error::UnexpectedEOB),
+            [first, second, ..] => Err(Error::UnexpectedOpcode(u16::from_be_bytes([
+                *first, *second,
+            ]))),
+        }
+    }
+}
+
+/// Operations of a picture with their offsets from its start, up to its end
+///
+/// Iteration stops after the first error.
+pub struct Operations<B> {
+    buf: B,
+    version: Version,
+    total_size: usize,
+    is_done: bool,
+}
+
+impl<B: Buf> Operations<B> {
+    /// Opcodes following the preamble, of a picture of the given size
+    pub(crate) fn new(buf: B, version: Version, total_size: usize) -> Self {
+        Self {
+            buf,
+            version,
+            total_size,
+            is_done: false,
+        }
+    }
+}
+
+impl<B: Buf> Iterator for Operations<B> {
+    type Item = Result<(usize, Operation)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+
+        let offset = self.total_size - self.buf.remaining();
+        let res = match self.buf.has_remaining() {
+            true => Operation::parse(&mut self.buf, self.version),
+            false => Err(Error::UnexpectedEOB),
+        };
+        self.is_done = matches!(res, Err(_) | Ok(Operation::OpEndPic));
+
+        Some(res.map(|op| (offset, op)))
+    }
+}
+
+/// Next operation, failing if it isn't of the expected opcode
+pub(crate) fn expect_op(
+    iter: &mut impl Iterator<Item = Result<Operation>>,
+    expected: Opcode,
+) -> Result<Operation> {
+    let op = iter.next().ok_or(Error::UnexpectedEOB)??;
+
+    if op.opcode() != expected {
+        return Err(Error::UnexpectedOpcode(op.opcode() as u16));
+    }
+
+    Ok(op)
+}
+
+/// Unpacked rows
+fn read_rows(buf: impl Buf, row_bytes: usize, height: usize) -> Result<Vec<u8>> {
+    let size = row_bytes * height;
+    let mut buf = ensure_remains_bytes(buf, size)?;
+
+    let mut ret = vec![0; size];
+    buf.copy_to_slice(&mut ret);
+
+    Ok(ret)
+}
+
+/// Color of 16 bits components
+fn read_rgb(buf: impl Buf) -> Result<[u16; 3]> {
+    let mut buf = ensure_remains_bytes(buf, 6)?;
+
+    Ok([buf.get_u16(), buf.get_u16(), buf.get_u16()])
+}
+
+/// Count prefixed text, as stored after the location of text opcodes
+fn read_text(mut buf: impl Buf) -> Result<String> {
+    let mut count = ensure_remains_bytes(&mut buf, 1)?.get_u8() as usize;
+
+    // no documentation of text format itself
+    // MYST.DAT:4001 isn't UTF-8
+
+    let mut decoder = WINDOWS_1252.new_decoder_without_bom_handling();
+    // characters above ASCII take more than a byte once in UTF-8
+    let mut text = String::with_capacity(
+        decoder
+            .max_utf8_buffer_length_without_replacement(count)
+            .unwrap_or(count),
+    );
+    loop {
+        let chunk = buf.chunk();
+        let chunk_size = cmp::min(chunk.len(), count);
+        let last_chunk = chunk_size == count;
+
+        let (ret, read) = decoder.decode_to_string_without_replacement(
+            &chunk[..chunk_size],
+            &mut text,
+            last_chunk,
+        );
+
+        buf.advance(read);
+        count -= read;
+
+        match ret {
+            encoding_rs::DecoderResult::InputEmpty if last_chunk => break,
+            encoding_rs::DecoderResult::InputEmpty => return Err(Error::UnexpectedEOB),
+            encoding_rs::DecoderResult::OutputFull => {
+                warn!("realocating string");
+                text.reserve(
+                    decoder
+                        .max_utf8_buffer_length_without_replacement(count)
+                        .unwrap_or(count)
+                        .max(1),
+                )
+            }
+            encoding_rs::DecoderResult::Malformed(_, _) => return Err(Error::InvalidCP1252Format),
+        }
+    }
+
+    Ok(text)
+}
+
+/// Rows of indexed pixels, of `row_bytes` once unpacked
+pub(crate) fn read_indexed_rows(
+    mut buf: impl Buf,
+    pix_map: &PixMap,
+    packed: bool,
+) -> Result<Vec<u8>> {
+    let (_, height) = pix_map.size();
+
+    // rows shorter than 8 bytes are never packed
+    if !packed || pix_map.row_bytes < 8 {
+        return read_rows(buf, pix_map.row_bytes.into(), height);
+    }
+
+    let mut ret = Vec::with_capacity(pix_map.row_bytes as usize * height);
+    for _ in 0..height {
+        let mut row = read_packed_row(&mut buf, pix_map.row_bytes)?;
+        row.resize(pix_map.row_bytes as usize, 0);
+        ret.extend(row);
+    }
+
+    Ok(ret)
+}
+
+/// Compressed row, prefixed by its size
+fn read_encoded_row(mut buf: impl Buf, row_bytes: u16) -> Result<Vec<u8>> {
+    let encoded_line_size = if row_bytes > 250 {
+        ensure_remains_bytes(&mut buf, 2)?.get_u16() as usize
+    } else {
+        ensure_remains_bytes(&mut buf, 1)?.get_u8() as usize
+    };
+
+    let mut encoded_line = vec![0; encoded_line_size];
+    ensure_remains_bytes(&mut buf, encoded_line_size)?.copy_to_slice(&mut encoded_line);
+
+    Ok(encoded_line)
+}
+
+/// Row compressed with PackBits, prefixed by its size
+fn read_packed_row(buf: impl Buf, row_bytes: u16) -> Result<Vec<u8>> {
+    let encoded_line = read_encoded_row(buf, row_bytes)?;
+
+    let mut decoder = packbits::Decoder::new();
+    let mut decoded = decoder.decode(encoded_line.as_slice());
+    let mut line = Vec::with_capacity(row_bytes as usize);
+    while decoded.has_remaining() {
+        let chunk = decoded.chunk();
+        line.extend_from_slice(chunk);
+        decoded.advance(chunk.len());
+    }
+    decoder.finalize()?;
+
+    Ok(line)
+}
+
+/// Row compressed with PackBits by 16 bits words, prefixed by its size
+fn read_packed_words_row(buf: impl Buf, row_bytes: u16) -> Result<Vec<u8>> {
+    let encoded_line = read_encoded_row(buf, row_bytes)?;
+
+    let mut encoded = encoded_line.as_slice();
+    let mut line = Vec::with_capacity(row_bytes as usize);
+    while let Some((&flag, rest)) = encoded.split_first() {
+        let (count, is_run) = match flag as i8 {
+            // no-op, without data
+            -128 => {
+                encoded = rest;
+                continue;
+            }
+            flag if flag < 0 => ((1 - flag as isize) as usize, true),
+            flag => (flag as usize + 1, false),
+        };
+        let size = if is_run { 2 } else { count * 2 };
+        let words = rest.get(..size).ok_or(Error::UnexpectedEOB)?;
+
+        match is_run {
+            true => (0..count).for_each(|_| line.extend_from_slice(words)),
+            false => line.extend_from_slice(words),
+        }
+        encoded = &rest[size..];
+    }
+
+    Ok(line)
+}
+
+/// Rows of direct pixels, without padding, as RGB555 for 16 bits pixels, RGBA32 with alpha and RGB24 otherwise
+fn read_direct_rows(mut buf: impl Buf, pix_map: &PixMap) -> Result<Vec<u8>> {
+    let (width, height) = pix_map.size();
+    let row_bytes = pix_map.row_bytes;
+    // small rows are never packed
+    let is_unpacked = pix_map.pack_type == 1 || row_bytes < 8;
+    if row_bytes == 0 {
+        return match width * height {
+            0 => Ok(Vec::new()),
+            _ => Err(Error::InvalidRowBytes(row_bytes)),
+        };
+    }
+
+    let components = (pix_map.components_count, pix_map.components_size);
+    match (pix_map.pixel_size, components) {
+        (16, (3, 5)) | (32, (3 | 4, 8)) => {}
+        (16 | 32, (count, size)) => return Err(Error::UnsupportedComponents(count, size)),
+        (pixel_size, _) => return Err(Error::UnsupportedPixelSize(pixel_size)),
+    }
+    // components of an output pixel, alpha moved last
+    let order: &[usize] = match pix_map.has_alpha() {
+        true => &[1, 2, 3, 0],
+        false => &[1, 2, 3],
+    };
+
+    let mut ret = Vec::with_capacity(width * height * order.len());
+    match (pix_map.pixel_size, pix_map.pack_type) {
+        (16, _) if is_unpacked => {
+            let rows = read_rows(&mut buf, row_bytes.into(), height)?;
+            for row in rows.chunks_exact(row_bytes as usize) {
+                ret.extend(row.iter().take(width * 2));
+            }
+        }
+        (16, 0 | 3) => {
+            for _ in 0..height {
+                let mut line = read_packed_words_row(&mut buf, row_bytes)?;
+                line.resize(width * 2, 0);
+                ret.extend(line);
+            }
+        }
+        (32, _) if is_unpacked => {
+            // pixels start with alpha, or an unused byte
+            let rows = read_rows(&mut buf, row_bytes.into(), height)?;
+            for row in rows.chunks_exact(row_bytes as usize) {
+                ret.extend(
+                    row.chunks_exact(4)
+                        .take(width)
+                        .flat_map(|pixel| order.iter().map(|&i| pixel[i])),
+                );
+            }
+        }
+        (32, 2) => {
+            // the alpha or unused byte is dropped, leaving opaque pixels
+            let rows = read_rows(&mut buf, width * 3, height)?;
+            match pix_map.has_alpha() {
+                true => ret.extend(
+                    rows.chunks_exact(3)
+                        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF]),
+                ),
+                false => ret = rows,
+            }
+        }
+        (32, 0 | 4) => {
+            let planes_count = pix_map.components_count as usize;
+            for _ in 0..height {
+                let line = read_packed_row(&mut buf, row_bytes)?;
+                if width == 0 {
+                    continue;
+                }
+
+                // each line is a plane by component: alpha if any, then red, green and blue
+                if line.len() < width * planes_count {
+                    return Err(Error::UnevenPlanes);
+                }
+                // same indexes as padded pixels, where there is no alpha plane
+                let planes: Vec<_> = line.chunks_exact(width).take(planes_count).collect();
+                let plane = |i: usize| planes[i + planes_count - 4];
+                for x in 0..width {
+                    ret.extend(order.iter().map(|&i| plane(i)[x]));
+                }
+            }
+        }
+        (_, pack_type) => return Err(Error::UnsupportedPackType(pack_type)),
+    }
+
+    Ok(ret)
+}
+
+/// Parsed opcode with its data, drawn or not
+#[non_exhaustive]
+pub enum Operation {
     Nop,
-    Clip {
-        size: u16,
-        bounding: Rectangle,
-    },
+    Clip(Region),
+    BkPat(Pattern),
     TxFont(i16),
     TxFace(u8),
+    TxMode(u16),
+    /// Fixed point extra width of spaces
+    SpExtra(i32),
+    /// Width and height of the pen
     PnSize(Point),
+    PnMode(u16),
+    PnPat(Pattern),
+    FillPat(Pattern),
+    /// Width and height of the corners of rounded rectangles
+    OvSize(Point),
+    /// Move of the coordinates origin
+    Origin {
+        dh: i16,
+        dv: i16,
+    },
     TxSize(i16),
+    /// Color of the original eight color QuickDraw
+    FgColor(u32),
+    BkColor(u32),
     TxRatio {
         numerator: Point,
         denominator: Point,
     },
     VersionOp,
+    BkPixPat(Pattern),
+    PnPixPat(Pattern),
+    FillPixPat(Pattern),
+    PnLocHFrac(u16),
+    /// Extra width of every character
+    ChExtra(i16),
+    RGBFgCol([u16; 3]),
+    RGBBkCol([u16; 3]),
+    HiliteMode,
+    HiliteColor([u16; 3]),
     DefHilite,
+    OpColor([u16; 3]),
+    Line {
+        from: Point,
+        to: Point,
+    },
+    /// Line from the pen location
+    LineFrom(Point),
+    ShortLine {
+        from: Point,
+        dh: i8,
+        dv: i8,
+    },
+    ShortLineFrom {
+        dh: i8,
+        dv: i8,
+    },
     LongText {
         location: Point,
         text: String,
     },
+    /// Text moved from the previous one
+    DHText {
+        dh: u8,
+        text: String,
+    },
+    DVText {
+        dv: u8,
+        text: String,
+    },
+    DHDVText {
+        dh: u8,
+        dv: u8,
+        text: String,
+    },
+    /// Name of the font numbered as in TxFont
+    FontName {
+        id: i16,
+        name: String,
+    },
+    /// Indexed pixels, rows of `row_bytes` once unpacked, drawn only inside the mask region
+    BitsRect {
+        packed: bool,
+        pix_map: PixMap,
+        color_table: Option<ColorTable>,
+        source: Rectangle,
+        destination: Rectangle,
+        mode: u16,
+        mask_region: Option<Region>,
+        pix_data: Vec<u8>,
+    },
+    /// Direct pixels, rows without padding as RGB555 for 16 bits pixels, RGBA32 with alpha and RGB24 otherwise
     DirectBitsRect {
         pix_map: PixMap,
         source: Rectangle,
         destination: Rectangle,
         mode: u16,
+        mask_region: Option<Region>,
         pix_data: Vec<u8>,
     },
+    Shape {
+        verb: Verb,
+        shape: Shape,
+    },
+    ShortComment(i16),
     LongComment {
         kind: i16,
         text: String,
@@ -82,24 +576,73 @@ pub(crate) enum Operation {
         source: Rectangle,
         accuracy: u32,
         mask: Option<Vec<u8>>,
+        image_description: ImageDescription,
         data: Vec<u8>,
     },
 }
 
 impl Operation {
-    pub(crate) const fn opcode(&self) -> Opcode {
+    pub fn opcode(&self) -> Opcode {
         match self {
             Self::Nop => Opcode::Nop,
-            Self::Clip { .. } => Opcode::Clip,
+            Self::Clip(_) => Opcode::Clip,
+            Self::BkPat(_) => Opcode::BkPat,
             Self::PnSize(_) => Opcode::PnSize,
+            Self::PnMode(_) => Opcode::PnMode,
+            Self::PnPat(_) => Opcode::PnPat,
+            Self::FillPat(_) => Opcode::FillPat,
+            Self::OvSize(_) => Opcode::OvSize,
+            Self::Origin { .. } => Opcode::Origin,
             Self::TxFont(_) => Opcode::TxFont,
             Self::TxFace(_) => Opcode::TxFace,
+            Self::TxMode(_) => Opcode::TxMode,
+            Self::SpExtra(_) => Opcode::SpExtra,
             Self::VersionOp => Opcode::VersionOp,
             Self::TxSize(_) => Opcode::TxSize,
+            Self::FgColor(_) => Opcode::FgColor,
+            Self::BkColor(_) => Opcode::BkColor,
             Self::TxRatio { .. } => Opcode::TxRatio,
+            Self::BkPixPat(_) => Opcode::BkPixPat,
+            Self::PnPixPat(_) => Opcode::PnPixPat,
+            Self::FillPixPat(_) => Opcode::FillPixPat,
+            Self::PnLocHFrac(_) => Opcode::PnLocHFrac,
+            Self::ChExtra(_) => Opcode::ChExtra,
+            Self::RGBFgCol(_) => Opcode::RGBFgCol,
+            Self::RGBBkCol(_) => Opcode::RGBBkCol,
+            Self::HiliteMode => Opcode::HiliteMode,
+            Self::HiliteColor(_) => Opcode::HiliteColor,
             Self::DefHilite => Opcode::DefHilite,
+            Self::OpColor(_) => Opcode::OpColor,
+            Self::Line { .. } => Opcode::Line,
+            Self::LineFrom(_) => Opcode::LineFrom,
+            Self::ShortLine { .. } => Opcode::ShortLine,
+            Self::ShortLineFrom { .. } => Opcode::ShortLineFrom,
+            Self::Shape { verb, shape } => {
+                Opcode::from_repr(shape.opcode(*verb)).expect("every shape opcode to be listed")
+            }
+            Self::ShortComment(_) => Opcode::ShortComment,
             Self::LongText { .. } => Opcode::LongText,
-            Self::DirectBitsRect { .. } => Opcode::DirectBitsRect,
+            Self::DHText { .. } => Opcode::DHText,
+            Self::DVText { .. } => Opcode::DVText,
+            Self::DHDVText { .. } => Opcode::DHDVText,
+            Self::FontName { .. } => Opcode::FontName,
+            Self::BitsRect {
+                packed,
+                mask_region,
+                ..
+            } => match (packed, mask_region.is_some()) {
+                (false, false) => Opcode::BitsRect,
+                (false, true) => Opcode::BitsRgn,
+                (true, false) => Opcode::PackBitsRect,
+                (true, true) => Opcode::PackBitsRgn,
+            },
+            Self::DirectBitsRect {
+                mask_region: None, ..
+            } => Opcode::DirectBitsRect,
+            Self::DirectBitsRect {
+                mask_region: Some(_),
+                ..
+            } => Opcode::DirectBitsRgn,
             Self::LongComment { .. } => Opcode::LongComment,
             Self::OpEndPic => Opcode::OpEndPic,
             Self::Version => Opcode::Version,
@@ -108,163 +651,193 @@ impl Operation {
         }
     }
 
-    pub(crate) fn parse(mut buf: impl Buf) -> Result<Self> {
+    pub(crate) fn parse(mut buf: impl Buf, version: Version) -> Result<Self> {
         let pos = buf.remaining();
 
-        let raw = buf.get_u16();
+        let raw = match version {
+            Version::V1 => ensure_remains_bytes(&mut buf, 1)?.get_u8() as u16,
+            Version::V2 => ensure_remains_bytes(&mut buf, 2)?.get_u16(),
+        };
         let opcode = Opcode::from_repr(raw).ok_or(Error::UnsupportedOpcode(raw))?;
 
         let op = match opcode {
             Opcode::Nop => Self::Nop,
-            Opcode::Clip => Self::Clip {
-                size: buf.get_u16(),
-                bounding: Rectangle::parse(&mut buf)?,
-            },
-            Opcode::TxFont => Self::TxFont(buf.get_i16()),
-            Opcode::TxFace => {
-                let ret = Self::TxFace(buf.get_u8());
-                skip_filler(&mut buf)?;
-                ret
-            }
+            Opcode::Clip => Self::Clip(Region::parse(&mut buf)?),
+            Opcode::BkPat => Self::BkPat(Pattern::parse(&mut buf)?),
+            Opcode::TxFont => Self::TxFont(ensure_remains_bytes(&mut buf, 2)?.get_i16()),
+            Opcode::TxFace => Self::TxFace(ensure_remains_bytes(&mut buf, 1)?.get_u8()),
+            Opcode::TxMode => Self::TxMode(ensure_remains_bytes(&mut buf, 2)?.get_u16()),
+            Opcode::SpExtra => Self::SpExtra(ensure_remains_bytes(&mut buf, 4)?.get_i32()),
             Opcode::PnSize => Self::PnSize(Point::parse(&mut buf)?),
-            Opcode::TxSize => Self::TxSize(buf.get_i16()),
+            Opcode::PnMode => Self::PnMode(ensure_remains_bytes(&mut buf, 2)?.get_u16()),
+            Opcode::PnPat => Self::PnPat(Pattern::parse(&mut buf)?),
+            Opcode::FillPat => Self::FillPat(Pattern::parse(&mut buf)?),
+            Opcode::OvSize => Self::OvSize(Point::parse(&mut buf)?),
+            Opcode::Origin => {
+                let mut buf = ensure_remains_bytes(&mut buf, 4)?;
+                Self::Origin {
+                    dh: buf.get_i16(),
+                    dv: buf.get_i16(),
+                }
+            }
+            Opcode::TxSize => Self::TxSize(ensure_remains_bytes(&mut buf, 2)?.get_i16()),
+            Opcode::FgColor => Self::FgColor(ensure_remains_bytes(&mut buf, 4)?.get_u32()),
+            Opcode::BkColor => Self::BkColor(ensure_remains_bytes(&mut buf, 4)?.get_u32()),
             Opcode::TxRatio => Self::TxRatio {
                 numerator: Point::parse(&mut buf)?,
                 denominator: Point::parse(&mut buf)?,
             },
-            Opcode::VersionOp => Self::VersionOp,
-            Opcode::DefHilite => Self::DefHilite,
-            Opcode::LongText => {
-                let location = Point::parse(&mut buf)?;
-                let mut count = buf.get_u8() as usize;
-                let need_filler = count % 2 == 0;
-
-                // no documentation of text format itself
-                // MYST.DAT:4001 isn't UTF-8
-
-                let mut decoder = WINDOWS_1252.new_decoder_without_bom_handling();
-                let mut text = String::with_capacity(count);
-                loop {
-                    let chunk = buf.chunk();
-                    let chunk_size = cmp::min(chunk.len(), count);
-                    let last_chunk = chunk_size == count;
-
-                    let (ret, read) = decoder.decode_to_string_without_replacement(
-                        &chunk[..chunk_size],
-                        &mut text,
-                        last_chunk,
-                    );
-
-                    buf.advance(read);
-                    count -= read;
-
-                    match ret {
-                        encoding_rs::DecoderResult::InputEmpty if last_chunk => break,
-                        encoding_rs::DecoderResult::InputEmpty => return Err(Error::UnexpectedEOB),
-                        encoding_rs::DecoderResult::OutputFull => {
-                            warn!("realocating string");
-                            text.reserve(1) // TODO how much to reserve?
-                        }
-                        encoding_rs::DecoderResult::Malformed(_, _) => {
-                            return Err(Error::InvalidCP1252Format)
-                        }
+            Opcode::VersionOp => {
+                // version 2 is given by the next opcode
+                if version == Version::V1 {
+                    let number = ensure_remains_bytes(&mut buf, 1)?.get_u8();
+                    if number != 1 {
+                        return Err(Error::UnsupportedVersion(number));
                     }
                 }
+                Self::VersionOp
+            }
+            Opcode::BkPixPat => Self::BkPixPat(Pattern::parse_pix_pat(&mut buf)?),
+            Opcode::PnPixPat => Self::PnPixPat(Pattern::parse_pix_pat(&mut buf)?),
+            Opcode::FillPixPat => Self::FillPixPat(Pattern::parse_pix_pat(&mut buf)?),
+            Opcode::PnLocHFrac => Self::PnLocHFrac(ensure_remains_bytes(&mut buf, 2)?.get_u16()),
+            Opcode::ChExtra => Self::ChExtra(ensure_remains_bytes(&mut buf, 2)?.get_i16()),
+            Opcode::RGBFgCol => Self::RGBFgCol(read_rgb(&mut buf)?),
+            Opcode::RGBBkCol => Self::RGBBkCol(read_rgb(&mut buf)?),
+            Opcode::HiliteMode => Self::HiliteMode,
+            Opcode::HiliteColor => Self::HiliteColor(read_rgb(&mut buf)?),
+            Opcode::DefHilite => Self::DefHilite,
+            Opcode::OpColor => Self::OpColor(read_rgb(&mut buf)?),
+            Opcode::Line => Self::Line {
+                from: Point::parse(&mut buf)?,
+                to: Point::parse(&mut buf)?,
+            },
+            Opcode::LineFrom => Self::LineFrom(Point::parse(&mut buf)?),
+            Opcode::ShortLine => {
+                let from = Point::parse(&mut buf)?;
+                let mut buf = ensure_remains_bytes(&mut buf, 2)?;
+                Self::ShortLine {
+                    from,
+                    dh: buf.get_i8(),
+                    dv: buf.get_i8(),
+                }
+            }
+            Opcode::ShortLineFrom => {
+                let mut buf = ensure_remains_bytes(&mut buf, 2)?;
+                Self::ShortLineFrom {
+                    dh: buf.get_i8(),
+                    dv: buf.get_i8(),
+                }
+            }
+            Opcode::ShortComment => {
+                Self::ShortComment(ensure_remains_bytes(&mut buf, 2)?.get_i16())
+            }
+            Opcode::LongText => Self::LongText {
+                location: Point::parse(&mut buf)?,
+                text: read_text(&mut buf)?,
+            },
+            Opcode::DHText => {
+                let dh = ensure_remains_bytes(&mut buf, 1)?.get_u8();
+                Self::DHText {
+                    dh,
+                    text: read_text(&mut buf)?,
+                }
+            }
+            Opcode::DVText => {
+                let dv = ensure_remains_bytes(&mut buf, 1)?.get_u8();
+                Self::DVText {
+                    dv,
+                    text: read_text(&mut buf)?,
+                }
+            }
+            Opcode::DHDVText => {
+                let (dh, dv) = {
+                    let mut buf = ensure_remains_bytes(&mut buf, 2)?;
+                    (buf.get_u8(), buf.get_u8())
+                };
+                Self::DHDVText {
+                    dh,
+                    dv,
+                    text: read_text(&mut buf)?,
+                }
+            }
+            Opcode::FontName => {
+                let size = ensure_remains_bytes(&mut buf, 2)?.get_u16() as usize;
+                let mut data = ensure_remains_bytes(&mut buf, size)?.take(size);
 
-                if need_filler
-                // count is a byte so we start at odd bytes
-                {
-                    skip_filler(&mut buf)?;
+                let id = ensure_remains_bytes(&mut data, 2)?.get_i16();
+                let length = ensure_remains_bytes(&mut data, 1)?.get_u8() as usize;
+                let mut name = vec![0; length];
+                ensure_remains_bytes(&mut data, length)?.copy_to_slice(&mut name);
+                data.advance(data.remaining());
+
+                Self::FontName {
+                    id,
+                    name: MACINTOSH.decode_without_bom_handling(&name).0.into_owned(),
                 }
+            }
+            Opcode::BitsRect | Opcode::BitsRgn | Opcode::PackBitsRect | Opcode::PackBitsRgn => {
+                let pix_map = PixMap::parse_bits(&mut buf)?;
+                let color_table = match pix_map.is_pix_map() {
+                    true => Some(ColorTable::parse(&mut buf)?),
+                    false => None,
+                };
 
-                Self::LongText { location, text }
+                let source = Rectangle::parse(&mut buf)?;
+                let destination = Rectangle::parse(&mut buf)?;
+                let mode = ensure_remains_bytes(&mut buf, 2)?.get_u16();
+                let mask_region = match opcode {
+                    Opcode::BitsRgn | Opcode::PackBitsRgn => Some(Region::parse(&mut buf)?),
+                    _ => None,
+                };
+
+                let packed = matches!(opcode, Opcode::PackBitsRect | Opcode::PackBitsRgn);
+                let pix_data = read_indexed_rows(&mut buf, &pix_map, packed)?;
+
+                Self::BitsRect {
+                    packed,
+                    pix_map,
+                    color_table,
+                    source,
+                    destination,
+                    mode,
+                    mask_region,
+                    pix_data,
+                }
             }
-            Opcode::DirectBitsRect => {
+            Opcode::DirectBitsRect | Opcode::DirectBitsRgn => {
                 let pix_map = PixMap::parse(&mut buf)?;
                 if pix_map.base_addr != 0xFF {
-                    panic!("incompatible base address")
+                    return Err(Error::UnsupportedBaseAddress(pix_map.base_addr));
                 }
 
                 let source = Rectangle::parse(&mut buf)?;
                 let destination = Rectangle::parse(&mut buf)?;
-                let mode = buf.get_u16();
-
-                let mut odd_bytes_count_read = false;
-
-                let bound_height = pix_map.bounds.bottom - pix_map.bounds.top;
-                let pix_data = match pix_map.pack_type {
-                    1 => {
-                        let size = pix_map.row_bytes as usize * bound_height as usize;
-
-                        let mut ret = vec![0; size];
-                        buf.copy_to_slice(&mut ret);
-                        odd_bytes_count_read = size % 2 == 1;
-
-                        ret
-                    }
-                    4 => {
-                        let mut ret = Vec::new(); // TODO capacity
-
-                        for _ in 0..bound_height {
-                            let encoded_line_size = if pix_map.row_bytes > 250 {
-                                buf.get_u16() as usize
-                            } else {
-                                odd_bytes_count_read = !odd_bytes_count_read;
-                                buf.get_u8() as usize
-                            };
-
-                            let mut encoded_line = vec![0; encoded_line_size];
-                            buf.copy_to_slice(&mut encoded_line);
-                            odd_bytes_count_read ^= encoded_line_size % 2 == 1;
-
-                            let mut decoder = packbits::Decoder::new();
-                            let mut decoded = decoder.decode(encoded_line.as_slice());
-                            let mut line = Vec::with_capacity(encoded_line_size);
-                            while decoded.has_remaining() {
-                                let chunk = decoded.chunk();
-                                line.extend_from_slice(chunk);
-                                decoded.advance(chunk.len());
-                            }
-                            decoder.finalize().unwrap(); // TODO don't panic
-
-                            // each line is somewhat planar encoding of color
-                            // first all the red, then all the green, then blue
-                            let (r, gb) = line.split_at(line.len() / 3);
-                            let (g, b) = gb.split_at(line.len() / 3);
-                            assert_eq!(r.len(), g.len());
-                            assert_eq!(g.len(), b.len());
-
-                            ret.extend(
-                                r.iter()
-                                    .zip(g.iter())
-                                    .zip(b.iter())
-                                    .flat_map(|((a, b), c)| [a, b, c]),
-                            );
-                        }
-
-                        ret
-                    }
-                    _ => todo!("unsupported pack type: {}", pix_map.pack_type),
+                let mode = ensure_remains_bytes(&mut buf, 2)?.get_u16();
+                let mask_region = match opcode {
+                    Opcode::DirectBitsRgn => Some(Region::parse(&mut buf)?),
+                    _ => None,
                 };
 
-                if odd_bytes_count_read {
-                    skip_filler(&mut buf)?;
-                }
+                let pix_data = read_direct_rows(&mut buf, &pix_map)?;
 
                 Self::DirectBitsRect {
                     pix_map,
                     source,
                     destination,
                     mode,
+                    mask_region,
                     pix_data,
                 }
             }
             Opcode::LongComment => {
-                let kind = buf.get_i16();
-                let size = buf.get_u16();
+                let (kind, size) = {
+                    let mut header = ensure_remains_bytes(&mut buf, 4)?;
+                    (header.get_i16(), header.get_u16() as usize)
+                };
 
-                let mut raw = vec![0u8; size as usize];
-                buf.copy_to_slice(&mut raw);
+                let mut raw = vec![0u8; size];
+                ensure_remains_bytes(&mut buf, size)?.copy_to_slice(&mut raw);
                 let text = WINDOWS_1252
                     .decode_without_bom_handling_and_without_replacement(&raw)
                     .ok_or(Error::InvalidCP1252Format)?;
@@ -277,10 +850,31 @@ impl Operation {
             Opcode::OpEndPic => Self::OpEndPic, // doc says 2 bytes extra but not reality
             Opcode::Version => Self::Version,
             Opcode::HeaderOp => {
+                let mut buf = ensure_remains_bytes(&mut buf, 24)?;
+
                 let version = buf.get_i16();
-                skip_reserved(&mut buf, 2)?;
-                let resolution = (buf.get_u32(), buf.get_u32());
-                let source_rect = Rectangle::parse(&mut buf)?;
+                let (resolution, source_rect) = match version {
+                    // extended, at the source resolution
+                    -2 => {
+                        skip_reserved(&mut buf, 2)?;
+                        let resolution = (buf.get_u32(), buf.get_u32());
+                        (resolution, Rectangle::parse(&mut buf)?)
+                    }
+                    // original, version as a long then fixed point left, top, right and bottom
+                    -1 => {
+                        buf.advance(2);
+                        let [left, top, right, bottom] =
+                            [(); 4].map(|_| (buf.get_i32() >> 16) as i16);
+                        let bounds = Rectangle {
+                            top,
+                            left,
+                            bottom,
+                            right,
+                        };
+                        ((72 << 16, 72 << 16), bounds)
+                    }
+                    _ => return Err(Error::UnsupportedHeaderVersion(version)),
+                };
                 skip_reserved(&mut buf, 4)?;
 
                 Self::HeaderOp {
@@ -292,27 +886,36 @@ impl Operation {
             Opcode::CompressedQuickTime => {
                 // https://web.archive.org/web/20030827061809/http://developer.apple.com/documentation/QuickTime/INMAC/QT/iqImageCompMgr.a.htm
 
-                let size = buf.get_u32();
-                if size % 2 != 0 {
-                    panic!("uneven size so padding is wrong")
+                let size = ensure_remains_bytes(&mut buf, 4)?.get_u32();
+                if !size.is_multiple_of(2) {
+                    // uneven size so padding is wrong
+                    return Err(Error::InvalidOpcodeSize(size));
                 }
 
-                let version = buf.get_u16();
-                let transformation = Matrix::parse(&mut buf)?;
-                let matte_size = buf.get_u32();
-                let matte_rect = Rectangle::parse(&mut buf)?;
-                let mode = buf.get_u16();
+                let (version, transformation, matte_size, matte_rect) = {
+                    let mut header = ensure_remains_bytes(&mut buf, 2 + 36 + 4 + 8)?;
+                    (
+                        header.get_u16(),
+                        Matrix::parse(&mut header)?,
+                        header.get_u32(),
+                        Rectangle::parse(&mut header)?,
+                    )
+                };
+                let mode = ensure_remains_bytes(&mut buf, 2)?.get_u16();
                 let source = Rectangle::parse(&mut buf)?;
-                let accuracy = buf.get_u32();
-                let mask_size = buf.get_u32();
+                let (accuracy, mask_size) = {
+                    let mut header = ensure_remains_bytes(&mut buf, 8)?;
+                    (header.get_u32(), header.get_u32())
+                };
 
                 if matte_size > 0 {
-                    panic!("doc not precise on how to handle matte")
+                    // doc not precise on how to handle matte
+                    return Err(Error::UnsupportedMatte);
                 }
 
                 let mask = if mask_size > 0 {
                     let mut ret = vec![0; mask_size as usize];
-                    buf.copy_to_slice(&mut ret);
+                    ensure_remains_bytes(&mut buf, ret.len())?.copy_to_slice(&mut ret);
                     Some(ret)
                 } else {
                     None
@@ -321,13 +924,15 @@ impl Operation {
                 let img_desc = ImageDescription::parse(&mut buf)?;
 
                 let mut data = vec![0; img_desc.data_size as usize];
-                buf.copy_to_slice(&mut data);
+                ensure_remains_bytes(&mut buf, data.len())?.copy_to_slice(&mut data);
 
                 // img_desc.data_size might not run to the end of the opcode
-                let diff =
-                    size as usize - img_desc.data_size as usize - 68 - ImageDescription::RAW_SIZE;
+                let diff = (size as usize)
+                    .checked_sub(img_desc.data_size as usize + 68 + ImageDescription::RAW_SIZE)
+                    .ok_or(Error::InvalidOpcodeSize(size))?;
                 if diff > 1 {
-                    panic!("too much padding")
+                    // too much padding
+                    return Err(Error::InvalidOpcodeSize(size));
                 }
                 if diff != 0 {
                     skip_filler(&mut buf)?;
@@ -341,20 +946,191 @@ impl Operation {
                     source,
                     accuracy,
                     mask,
+                    image_description: img_desc,
                     data,
                 }
             }
+            // every other opcode draws a shape
+            _ => {
+                let (verb, shape) = Shape::parse(raw, &mut buf)?;
+                Self::Shape { verb, shape }
+            }
         };
 
-        assert_eq!(op.opcode(), opcode, "wrong operation returned for opcode",);
-        assert!(
-            (pos - buf.remaining()) % 2 == 0,
-            "{} should be 2 bytes aligned",
-            op.opcode(),
-        );
+        // an invariant of the match above, whatever the input
+        debug_assert_eq!(op.opcode(), opcode, "wrong operation returned for opcode");
+
+        // only version 2 opcodes are word aligned
+        if version == Version::V2 && !(pos - buf.remaining()).is_multiple_of(2) {
+            skip_filler(&mut buf)?;
+        }
 
         Ok(op)
     }
+
+    /// Text carried by the operation, drawn or not
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Self::LongText { text, .. }
+            | Self::DHText { text, .. }
+            | Self::DVText { text, .. }
+            | Self::DHDVText { text, .. }
+            | Self::LongComment { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Human readable description of the fields, for debugging
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Nop
+            | Self::VersionOp
+            | Self::HiliteMode
+            | Self::DefHilite
+            | Self::OpEndPic
+            | Self::Version => vec![],
+            Self::Clip(region) => region.fields(),
+            Self::BkPat(pattern)
+            | Self::PnPat(pattern)
+            | Self::FillPat(pattern)
+            | Self::BkPixPat(pattern)
+            | Self::PnPixPat(pattern)
+            | Self::FillPixPat(pattern) => pattern.fields(),
+            Self::TxFont(font) => vec![("font", font.to_string())],
+            Self::TxFace(face) => vec![("face", format!("{:08b}", face))],
+            Self::TxMode(mode) => vec![("mode", mode.to_string())],
+            Self::SpExtra(extra) => vec![("extra", format!("{:.2}", *extra as f64 / 65536.))],
+            Self::ChExtra(extra) => vec![("extra", extra.to_string())],
+            Self::PnSize(size) | Self::OvSize(size) => vec![("size", size.to_string())],
+            Self::PnMode(mode) => vec![("mode", mode.to_string())],
+            Self::Origin { dh, dv } => vec![("offset", format!("({}, {})", dh, dv))],
+            Self::TxSize(size) => vec![("size", size.to_string())],
+            Self::FgColor(color) | Self::BkColor(color) => vec![("color", color.to_string())],
+            Self::PnLocHFrac(fraction) => vec![("fraction", format!("{:04x}", fraction))],
+            Self::RGBFgCol(color)
+            | Self::RGBBkCol(color)
+            | Self::HiliteColor(color)
+            | Self::OpColor(color) => vec![(
+                "color",
+                format!("#{:04x}{:04x}{:04x}", color[0], color[1], color[2]),
+            )],
+            Self::Line { from, to } => vec![("from", from.to_string()), ("to", to.to_string())],
+            Self::LineFrom(to) => vec![("to", to.to_string())],
+            Self::ShortLine { from, dh, dv } => vec![
+                ("from", from.to_string()),
+                ("offset", format!("({}, {})", dh, dv)),
+            ],
+            Self::ShortLineFrom { dh, dv } => vec![("offset", format!("({}, {})", dh, dv))],
+            Self::Shape { verb: _, shape } => shape.fields(),
+            Self::ShortComment(kind) => vec![("kind", kind.to_string())],
+            Self::TxRatio {
+                numerator,
+                denominator,
+            } => vec![
+                ("numerator", numerator.to_string()),
+                ("denominator", denominator.to_string()),
+            ],
+            Self::LongText { location, text } => vec![
+                ("location", location.to_string()),
+                ("text", format!("{:?}", text)),
+            ],
+            Self::DHText { dh, text } => vec![
+                ("offset", format!("({}, 0)", dh)),
+                ("text", format!("{:?}", text)),
+            ],
+            Self::DVText { dv, text } => vec![
+                ("offset", format!("(0, {})", dv)),
+                ("text", format!("{:?}", text)),
+            ],
+            Self::DHDVText { dh, dv, text } => vec![
+                ("offset", format!("({}, {})", dh, dv)),
+                ("text", format!("{:?}", text)),
+            ],
+            Self::FontName { id, name } => {
+                vec![("font", id.to_string()), ("name", format!("{:?}", name))]
+            }
+            Self::BitsRect {
+                packed: _,
+                pix_map,
+                color_table,
+                source,
+                destination,
+                mode,
+                mask_region,
+                pix_data,
+            } => {
+                let mut ret = pix_map.fields();
+                ret.extend(color_table.iter().flat_map(ColorTable::fields));
+                ret.extend([
+                    ("source", source.to_string()),
+                    ("destination", destination.to_string()),
+                    ("mode", mode.to_string()),
+                ]);
+                ret.extend(mask_region.iter().map(|r| ("mask region", r.to_string())));
+                ret.push(("decoded size", pix_data.len().to_string()));
+                ret
+            }
+            Self::DirectBitsRect {
+                pix_map,
+                source,
+                destination,
+                mode,
+                mask_region,
+                pix_data,
+            } => {
+                let mut ret = pix_map.fields();
+                ret.extend([
+                    ("source", source.to_string()),
+                    ("destination", destination.to_string()),
+                    ("mode", mode.to_string()),
+                ]);
+                ret.extend(mask_region.iter().map(|r| ("mask region", r.to_string())));
+                ret.push(("decoded size", pix_data.len().to_string()));
+                ret
+            }
+            Self::LongComment { kind, text } => {
+                vec![("kind", kind.to_string()), ("text", format!("{:?}", text))]
+            }
+            Self::HeaderOp {
+                version,
+                resolution,
+                source,
+            } => vec![
+                ("version", version.to_string()),
+                (
+                    "resolution",
+                    format!("{}x{}", resolution.0 >> 16, resolution.1 >> 16),
+                ),
+                ("source", source.to_string()),
+            ],
+            Self::CompressedQuickTime {
+                version,
+                transformation,
+                matte_rect,
+                mode,
+                source,
+                accuracy,
+                mask,
+                image_description,
+                data: _,
+            } => {
+                let mut ret = vec![
+                    ("version", version.to_string()),
+                    ("transformation", transformation.to_string()),
+                    ("matte", matte_rect.to_string()),
+                    ("mode", mode.to_string()),
+                    ("source", source.to_string()),
+                    ("accuracy", accuracy.to_string()),
+                    (
+                        "mask size",
+                        mask.as_ref().map(Vec::len).unwrap_or(0).to_string(),
+                    ),
+                ];
+                ret.extend(image_description.fields());
+                ret
+            }
+        }
+    }
 }
 
 impl fmt::Display for Operation {
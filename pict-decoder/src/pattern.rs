@@ -0,0 +1,146 @@
+use bytes::Buf;
+
+use crate::{
+    color_table::ColorTable,
+    image::{Image, PixelFormat},
+    operation::read_indexed_rows,
+    pixmap::PixMap,
+    utils::ensure_remains_bytes,
+    Result,
+};
+
+/// What a pattern draws at a pixel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Ink {
+    Fore,
+    Back,
+    Color([u8; 3]),
+}
+
+/// Repeated tile drawn by shapes, aligned on the origin
+#[derive(Clone)]
+pub enum Pattern {
+    /// 8x8 bits, set ones drawn with the foreground color and others with the background
+    Bits([u8; 8]),
+    /// Tile of colors
+    Pixels(Image),
+    /// Solid color, dithered by QuickDraw when it can't be displayed
+    Dither([u8; 3]),
+}
+
+impl Pattern {
+    const BITS_SIZE: usize = 8;
+
+    /// Only foreground, as the default pen and fill patterns
+    pub(crate) const BLACK: Self = Self::Bits([0xFF; 8]);
+    /// Only background, as the default background pattern
+    pub(crate) const WHITE: Self = Self::Bits([0x00; 8]);
+
+    pub(crate) fn parse(buf: impl Buf) -> Result<Self> {
+        let mut buf = ensure_remains_bytes(buf, Self::BITS_SIZE)?;
+
+        let mut bits = [0; Self::BITS_SIZE];
+        buf.copy_to_slice(&mut bits);
+
+        Ok(Self::Bits(bits))
+    }
+
+    /// PixPat record, keeping only its colors
+    pub(crate) fn parse_pix_pat(mut buf: impl Buf) -> Result<Self> {
+        let kind = ensure_remains_bytes(&mut buf, 2)?.get_u16();
+        let bits = Self::parse(&mut buf)?;
+
+        match kind {
+            1 => {
+                let pix_map = PixMap::parse_bits(&mut buf)?;
+                let palette = match pix_map.is_pix_map() {
+                    true => ColorTable::parse(&mut buf)?.palette(),
+                    false => vec![[0xFF; 3], [0; 3]],
+                };
+                let pix_data = read_indexed_rows(&mut buf, &pix_map, true)?;
+
+                let (width, height) = pix_map.size();
+                let image = Image::from_indexes(
+                    width,
+                    height,
+                    pix_map.row_bytes as usize,
+                    pix_map.pixel_size,
+                    palette,
+                    pix_data,
+                )?;
+
+                Ok(Self::Pixels(image.convert(PixelFormat::RGB24)))
+            }
+            2 => {
+                let mut rgb = ensure_remains_bytes(&mut buf, 6)?;
+                Ok(Self::Dither([0; 3].map(|_| (rgb.get_u16() >> 8) as u8)))
+            }
+            _ => Ok(bits),
+        }
+    }
+
+    /// Ink at the position, the pattern being repeated from the origin
+    pub(crate) fn ink(&self, x: i32, y: i32) -> Ink {
+        match self {
+            Self::Bits(bits) => {
+                let row = bits[y.rem_euclid(8) as usize];
+                match row & (0x80 >> x.rem_euclid(8)) {
+                    0 => Ink::Back,
+                    _ => Ink::Fore,
+                }
+            }
+            Self::Pixels(image) if image.width() == 0 || image.height() == 0 => Ink::Fore,
+            Self::Pixels(image) => {
+                let [r, g, b, _] = image.pixel(
+                    x.rem_euclid(image.width() as i32) as usize,
+                    y.rem_euclid(image.height() as i32) as usize,
+                );
+                Ink::Color([r, g, b])
+            }
+            Self::Dither(color) => Ink::Color(*color),
+        }
+    }
+
+    /// Human readable description of the fields, for debugging
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Bits(bits) => vec![(
+                "pattern",
+                bits.iter().map(|b| format!("{:02x}", b)).collect(),
+            )],
+            Self::Pixels(image) => vec![(
+                "pattern size",
+                format!("{}x{}", image.width(), image.height()),
+            )],
+            Self::Dither([r, g, b]) => {
+                vec![("pattern color", format!("#{:02x}{:02x}{:02x}", r, g, b))]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_bits() {
+        let pattern = Pattern::Bits([0x80, 0, 0, 0, 0, 0, 0, 0x01]);
+
+        assert_eq!(pattern.ink(0, 0), Ink::Fore);
+        assert_eq!(pattern.ink(1, 0), Ink::Back);
+        assert_eq!(pattern.ink(8, 16), Ink::Fore);
+        assert_eq!(pattern.ink(-1, -1), Ink::Fore);
+    }
+
+    #[test]
+    fn dither_is_solid() {
+        let mut raw = 2u16.to_be_bytes().to_vec();
+        raw.extend_from_slice(&[0x55; 8]);
+        raw.extend_from_slice(&[0xFF, 0xFF, 0x80, 0x00, 0x00, 0x00]);
+
+        let pattern = Pattern::parse_pix_pat(raw.as_slice()).unwrap();
+
+        assert_eq!(pattern.ink(3, 5), Ink::Color([0xFF, 0x80, 0]));
+    }
+}
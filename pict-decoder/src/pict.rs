@@ -4,8 +4,15 @@ use bytes::Buf;
 use tracing::trace;
 
 use crate::{
-    operation::{Opcode, Operation},
+    canvas::Canvas,
+    font::Fonts,
+    header::PictureHeader,
+    image::{Image, PixelFormat},
+    operation::{Operation, Operations, Version},
+    quicktime::{self, ImageDescription},
     rectangle::Rectangle,
+    region::Mask,
+    utils::ensure_remains_bytes,
     Error, Result,
 };
 
@@ -13,87 +20,284 @@ use crate::{
 // https://preterhuman.net/macstuff/insidemac/QuickDraw/QuickDraw-458.html
 
 pub enum PICT {
+    /// QuickTime compressed picture, kept as is without the `jpeg` feature
     JPEG(Vec<u8>),
-    RGB24 {
-        width: usize,
-        height: usize,
-        data: Vec<u8>,
-    },
+    Image(Image),
 }
 
 impl PICT {
-    fn expect_op(
-        iter: &mut impl Iterator<Item = Result<Operation>>,
-        expected: Opcode,
-    ) -> Result<Operation> {
-        let op = iter.next().ok_or(Error::UnexpectedEOB)??;
-
-        if op.opcode() != expected {
-            return Err(Error::UnexpectedOpcode(op.opcode() as u16));
+    /// Pixels of the picture
+    ///
+    /// JPEG pictures are only decoded with the `jpeg` feature, enabled by default.
+    pub fn to_image(&self) -> Result<Image> {
+        match self {
+            Self::JPEG(_) => Err(Error::UnsupportedJPEG),
+            Self::Image(image) => Ok(image.clone()),
         }
-
-        Ok(op)
     }
 
-    pub fn parse(mut buf: impl Buf) -> Result<PICT> {
-        use Error::*;
+    /// Parse what comes before the opcodes, returning the picture size and frame
+    pub(crate) fn parse_preamble(mut buf: impl Buf) -> Result<(u16, Rectangle)> {
+        const EMPTY_HEADER_SIZE: usize = 512;
 
-        let mut empty_header = [0u8; 512];
+        let mut buf = ensure_remains_bytes(&mut buf, EMPTY_HEADER_SIZE + 2 + 8)?;
+
+        let mut empty_header = [0u8; EMPTY_HEADER_SIZE];
         buf.copy_to_slice(&mut empty_header);
         if !empty_header.into_iter().all(|b| b == 0) {
-            return Err(NonEmptyHeader);
+            return Err(Error::NonEmptyHeader);
         }
 
-        let _size = buf.get_u16();
-        let _bounding_rect = Rectangle::parse(&mut buf)?;
+        let size = buf.get_u16();
+        let bounding_rect = Rectangle::parse(&mut buf)?;
+
+        Ok((size, bounding_rect))
+    }
 
-        let mut opcodes = iter::from_fn(|| buf.has_remaining().then(|| Operation::parse(&mut buf)));
+    fn from_quicktime(
+        image_description: &ImageDescription,
+        source: &Rectangle,
+        data: Vec<u8>,
+    ) -> Result<Self> {
+        if cfg!(not(feature = "jpeg")) && &image_description.compressor_type == b"jpeg" {
+            return Ok(Self::JPEG(data));
+        }
 
-        Self::expect_op(&mut opcodes, Opcode::VersionOp)?;
-        Self::expect_op(&mut opcodes, Opcode::Version)?;
-        if let Operation::HeaderOp { version: v, .. } =
-            Self::expect_op(&mut opcodes, Opcode::HeaderOp)?
-        {
-            if v != -2 {
-                return Err(UnsupportedHeaderVersion(v));
-            }
+        quicktime::decode(image_description, source, &data).map(Self::Image)
+    }
+
+    /// Paint white the pixels hidden by the masks, with the image drawn at its destination
+    fn apply_masks<'a>(
+        image: Image,
+        destination: &Rectangle,
+        masks: impl IntoIterator<Item = &'a Mask>,
+    ) -> Image {
+        let Some(mask) = masks
+            .into_iter()
+            .cloned()
+            .reduce(|acc, mask| acc.intersection(&mask))
+        else {
+            return image;
+        };
+
+        let (width, height) = (image.width(), image.height());
+        let (dest_width, dest_height) = (destination.width(), destination.height());
+        let is_visible = |x: usize, y: usize| {
+            mask.contains(
+                destination.left as i32 + (x * dest_width / width.max(1)) as i32,
+                destination.top as i32 + (y * dest_height / height.max(1)) as i32,
+            )
+        };
+
+        let is_hidden = (0..height).any(|y| (0..width).any(|x| !is_visible(x, y)));
+        match is_hidden {
+            true => image.masked(is_visible, [0xFF; 3]),
+            false => image,
+        }
+    }
+
+    /// Draw the bitmap at its destination, returning it with the count of draws if it is alone
+    fn draw_bitmap(
+        canvas: &mut Canvas,
+        sole: Option<(Self, usize)>,
+        pict: Self,
+        (destination, mode): (&Rectangle, u16),
+        mask_region: Option<&Mask>,
+        frame: &Rectangle,
+    ) -> Result<Option<(Self, usize)>> {
+        // without decoder, JPEG can't be drawn with anything else
+        if let Some((Self::JPEG(_), _)) = sole {
+            return Err(Error::UnsupportedJPEG);
         }
+        // as drawn on white, only copies keep the bitmap as is
+        let is_copy = matches!(mode, 0 | 64);
+        let is_alone = !canvas.is_drawn() && destination == frame && is_copy;
+
+        let image = match pict {
+            Self::JPEG(data) if is_alone => return Ok(Some((Self::JPEG(data), canvas.draws()))),
+            Self::JPEG(_) => return Err(Error::UnsupportedJPEG),
+            Self::Image(image) => image,
+        };
+
+        canvas.draw_image(&image, destination, mode, mask_region);
+
+        let fits = (image.width(), image.height()) == (destination.width(), destination.height());
+        Ok((is_alone && fits).then(|| {
+            let image = Self::apply_masks(
+                image,
+                destination,
+                canvas.clip().into_iter().chain(mask_region),
+            );
+            (Self::Image(image), canvas.draws())
+        }))
+    }
 
-        let mut ret = None;
+    /// Operations of the picture, its header included, with their offsets from its start
+    ///
+    /// Contrary to [`PICT::parse`], nothing is drawn and the order of the opcodes isn't checked.
+    pub fn operations<B: Buf>(mut buf: B) -> Result<Operations<B>> {
+        let total_size = buf.remaining();
+
+        Self::parse_preamble(&mut buf)?;
+        let version = Version::detect(&buf)?;
+
+        Ok(Operations::new(buf, version, total_size))
+    }
+
+    pub fn parse(buf: impl Buf) -> Result<PICT> {
+        Self::parse_with_fonts(buf, &Fonts::default())
+    }
+
+    /// Parse, drawing text with the given fonts or the embedded one
+    pub fn parse_with_fonts(mut buf: impl Buf, fonts: &Fonts) -> Result<PICT> {
+        use Error::*;
+
+        let header = PictureHeader::parse(&mut buf)?;
+        let version = header.opcodes_version();
+
+        let mut opcodes = iter::from_fn(|| {
+            buf.has_remaining()
+                .then(|| Operation::parse(&mut buf, version))
+        });
+
+        // bitmap filling the frame alone, kept as decoded unless drawn over
+        let mut sole = None;
+        let mut canvas = Canvas::new(header.frame, fonts);
         for res in opcodes.by_ref() {
             let op = res?;
             trace!("exec op: {}", op);
 
             match op {
                 Operation::Nop => {}
-                Operation::DefHilite
-                | Operation::Clip { .. }
+                Operation::Clip(_)
+                | Operation::BkPat(_)
+                | Operation::PnSize(_)
+                | Operation::PnMode(_)
+                | Operation::PnPat(_)
+                | Operation::FillPat(_)
+                | Operation::OvSize(_)
+                | Operation::Origin { .. }
+                | Operation::FgColor(_)
+                | Operation::BkColor(_)
+                | Operation::BkPixPat(_)
+                | Operation::PnPixPat(_)
+                | Operation::FillPixPat(_)
+                | Operation::RGBFgCol(_)
+                | Operation::RGBBkCol(_)
+                | Operation::Line { .. }
+                | Operation::LineFrom(_)
+                | Operation::ShortLine { .. }
+                | Operation::ShortLineFrom { .. }
+                | Operation::Shape { .. }
                 | Operation::TxFont(_)
                 | Operation::TxFace(_)
-                | Operation::PnSize(_)
+                | Operation::TxMode(_)
+                | Operation::SpExtra(_)
                 | Operation::TxSize(_)
                 | Operation::TxRatio { .. }
+                | Operation::ChExtra(_)
                 | Operation::LongText { .. }
+                | Operation::DHText { .. }
+                | Operation::DVText { .. }
+                | Operation::DHDVText { .. }
+                | Operation::FontName { .. }
+                | Operation::OpColor(_) => canvas.execute(&op),
+                Operation::DefHilite
+                | Operation::HiliteMode
+                | Operation::HiliteColor(_)
+                | Operation::PnLocHFrac(_)
+                | Operation::ShortComment(_)
                 | Operation::LongComment { .. } => {} // TODO anything?
-                Operation::CompressedQuickTime { data, .. } => {
-                    if ret.is_some() {
-                        panic!("already got an image")
-                    }
-                    ret = Some(Self::JPEG(data))
+                Operation::CompressedQuickTime {
+                    transformation,
+                    source,
+                    mode,
+                    accuracy,
+                    image_description,
+                    data,
+                    ..
+                } => {
+                    // the source is mapped from the image's coordinates, as cropped to it
+                    let (pict, destination) =
+                        match Self::from_quicktime(&image_description, &source, data)? {
+                            Self::Image(image) => {
+                                let (image, destination) = transformation.place(
+                                    image,
+                                    (source.left.max(0), source.top.max(0)),
+                                    accuracy >= quicktime::NORMAL_QUALITY,
+                                );
+                                (Self::Image(image), destination)
+                            }
+                            jpeg => (jpeg, source),
+                        };
+                    sole = Self::draw_bitmap(
+                        &mut canvas,
+                        sole,
+                        pict,
+                        (&destination, mode),
+                        None,
+                        &header.frame,
+                    )?
+                }
+                Operation::BitsRect {
+                    pix_map,
+                    color_table,
+                    destination,
+                    mode,
+                    mask_region,
+                    pix_data,
+                    ..
+                } => {
+                    // BitMaps are black on white
+                    let palette = match color_table {
+                        Some(color_table) => color_table.palette(),
+                        None => vec![[0xFF; 3], [0; 3]],
+                    };
+                    let (width, height) = pix_map.size();
+                    let image = Image::from_indexes(
+                        width,
+                        height,
+                        pix_map.row_bytes as usize,
+                        pix_map.pixel_size,
+                        palette,
+                        pix_data,
+                    )?;
+                    let mask_region = mask_region.map(|region| region.mask(destination.area()));
+                    sole = Self::draw_bitmap(
+                        &mut canvas,
+                        sole,
+                        Self::Image(image),
+                        (&destination, mode),
+                        mask_region.as_ref(),
+                        &header.frame,
+                    )?
                 }
                 Operation::DirectBitsRect {
+                    pix_map,
                     pix_data,
                     destination,
+                    mode,
+                    mask_region,
                     ..
                 } => {
-                    if ret.is_some() {
-                        panic!("already got an image")
-                    }
-                    ret = Some(Self::RGB24 {
-                        width: (destination.right - destination.left) as usize,
-                        height: (destination.bottom - destination.top) as usize,
-                        data: pix_data,
-                    })
+                    let (width, height) = pix_map.size();
+                    let image = match pix_map.pixel_size {
+                        16 => Image::new(width, height, PixelFormat::RGB555, pix_data)?
+                            .convert(PixelFormat::RGB24),
+                        _ if pix_map.has_alpha() => {
+                            Image::new(width, height, PixelFormat::RGBA32, pix_data)?
+                        }
+                        _ => Image::new(width, height, PixelFormat::RGB24, pix_data)?,
+                    };
+                    let mask_region = mask_region.map(|region| region.mask(destination.area()));
+                    sole = Self::draw_bitmap(
+                        &mut canvas,
+                        sole,
+                        Self::Image(image),
+                        (&destination, mode),
+                        mask_region.as_ref(),
+                        &header.frame,
+                    )?
                 }
                 Operation::VersionOp | Operation::Version | Operation::HeaderOp { .. } => {
                     return Err(UnexpectedOpcode(op.opcode() as u16))
@@ -106,6 +310,455 @@ impl PICT {
             return Err(DataRemaining);
         }
 
-        ret.ok_or(UnableToFindImage)
+        match sole {
+            Some((pict, draws)) if draws == canvas.draws() => Ok(pict),
+            Some((Self::JPEG(_), _)) => Err(UnsupportedJPEG),
+            _ if canvas.is_drawn() => canvas.into_image().map(Self::Image),
+            _ => Err(UnableToFindImage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{operation::Opcode, tests::PictBuilder};
+
+    use super::*;
+
+    #[test]
+    fn packed_indexed() {
+        let palette = [[0xFF, 0, 0], [0, 0xFF, 0], [0, 0, 0xFF]];
+        let mut rows = vec![0u8; 2 * 10];
+        rows[..3].copy_from_slice(&[0, 1, 2]);
+        rows[10..13].copy_from_slice(&[2, 1, 0]);
+
+        for packed in [false, true] {
+            let raw = PictBuilder::new(3, 2)
+                .bits_rect(packed, (3, 2, 8), &palette, &rows)
+                .build();
+
+            let image = PICT::parse(raw.as_slice())
+                .and_then(|pict| pict.to_image())
+                .expect("to decode")
+                .convert(PixelFormat::RGB24);
+
+            assert_eq!(
+                image.data(),
+                [255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 255, 0, 255, 0, 255, 0, 0]
+            );
+        }
+    }
+
+    #[test]
+    fn clip_and_mask_region() {
+        // clip hides the right column, the mask region the bottom left pixel
+        let mut clip = 10u16.to_be_bytes().to_vec();
+        clip.extend(crate::tests::rect(0, 0, 2, 2));
+        let mut mask = 28u16.to_be_bytes().to_vec();
+        for value in [0i16, 0, 2, 3, 0, 0, 3, 0x7FFF, 1, 0, 1, 0x7FFF, 0x7FFF] {
+            mask.extend_from_slice(&value.to_be_bytes());
+        }
+        let mut content = vec![];
+        let mut builder = PictBuilder::new(3, 2).op(0x0001, &clip);
+        builder = builder.bits_rect(false, (3, 2, 8), &[[0; 3]], &[0; 2 * 4]);
+        let mut raw = builder.build();
+        // turn the BitsRect into a BitsRgn, inserting the mask after the mode
+        let op = raw.windows(2).rposition(|w| w == [0x00, 0x90]).unwrap();
+        raw[op + 1] = 0x91;
+        let pix_data = raw.len() - 2 - 2 * 4;
+        content.extend_from_slice(&raw[..pix_data]);
+        content.extend_from_slice(&mask);
+        content.extend_from_slice(&raw[pix_data..]);
+
+        let image = PICT::parse(content.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        let visible: Vec<_> = (0..2)
+            .flat_map(|y| (0..3).map(move |x| (x, y)))
+            .map(|(x, y)| image.pixel(x, y) == [0, 0, 0, 0xFF])
+            .collect();
+        assert_eq!(visible, [true, true, false, false, true, false]);
+    }
+
+    fn decode_direct(size: (u16, u16, u16), packing: (u16, u16), data: &[u8]) -> Image {
+        decode_direct_with_components(size, packing, 3, data)
+    }
+
+    fn decode_direct_with_components(
+        size: (u16, u16, u16),
+        packing: (u16, u16),
+        components_count: u16,
+        data: &[u8],
+    ) -> Image {
+        let raw = PictBuilder::new(size.0, size.1)
+            .direct_bits(size, packing, components_count, data)
+            .build();
+
+        PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode")
+    }
+
+    #[test]
+    fn direct_padded_pixels() {
+        // rows are padded too
+        let image = decode_direct((2, 1, 32), (1, 12), &[0, 1, 2, 3, 0, 4, 5, 6, 0, 0, 0, 0]);
+
+        assert_eq!(*image.format(), PixelFormat::RGB24);
+        assert_eq!(image.data(), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn direct_without_pad_byte() {
+        let image = decode_direct((2, 2, 32), (2, 8), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+
+        assert_eq!(image.data(), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn direct_rgb555() {
+        let image = decode_direct((2, 1, 16), (1, 4), &[0x7C, 0x00, 0x00, 0x1F]);
+
+        assert_eq!(*image.format(), PixelFormat::RGB24);
+        assert_eq!(image.data(), [0xFF, 0, 0, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn direct_packed_words() {
+        // a run of three red words then a literal green word
+        let data = [6, (-2i8) as u8, 0x7C, 0x00, 0, 0x03, 0xE0];
+
+        let image = decode_direct((4, 1, 16), (3, 8), &data);
+
+        let pixels: Vec<_> = (0..4).map(|x| image.pixel(x, 0)).collect();
+        assert_eq!(pixels[..3], [[0xFF, 0, 0, 0xFF]; 3]);
+        assert_eq!(pixels[3], [0, 0xFF, 0, 0xFF]);
+    }
+
+    #[test]
+    fn direct_packed_words_no_op() {
+        // a no-op between a red run and a literal green word
+        let data = [7, (-2i8) as u8, 0x7C, 0x00, 0x80, 0, 0x03, 0xE0];
+
+        let image = decode_direct((4, 1, 16), (3, 8), &data);
+
+        let pixels: Vec<_> = (0..4).map(|x| image.pixel(x, 0)).collect();
+        assert_eq!(pixels[..3], [[0xFF, 0, 0, 0xFF]; 3]);
+        assert_eq!(pixels[3], [0, 0xFF, 0, 0xFF]);
+    }
+
+    #[test]
+    fn direct_alpha_plane() {
+        // alpha, red, green then blue planes
+        let data = [9, 7, 0x80, 0xFF, 1, 2, 3, 4, 5, 6];
+
+        let image = decode_direct_with_components((2, 1, 32), (4, 8), 4, &data);
+
+        assert_eq!(*image.format(), PixelFormat::RGBA32);
+        assert_eq!(image.data(), [1, 3, 5, 0x80, 2, 4, 6, 0xFF]);
+    }
+
+    #[test]
+    fn direct_wide_pack_type_2() {
+        // rows of more than 65535 bytes
+        let width = 21846;
+        let data: Vec<_> = (0..width * 3).map(|i| i as u8).collect();
+
+        let image = decode_direct((width as u16, 1, 32), (2, 8), &data);
+
+        assert_eq!(image.data(), data);
+    }
+
+    #[test]
+    fn direct_without_row_bytes() {
+        let raw = PictBuilder::new(1, 1)
+            .direct_bits((1, 1, 32), (1, 0), 3, &[])
+            .build();
+
+        assert!(matches!(
+            PICT::parse(raw.as_slice()),
+            Err(Error::InvalidRowBytes(0))
+        ));
+    }
+
+    #[test]
+    fn direct_padded_alpha() {
+        let image = decode_direct_with_components((1, 1, 32), (1, 4), 4, &[0x40, 1, 2, 3]);
+
+        assert_eq!(image.data(), [1, 2, 3, 0x40]);
+    }
+
+    #[test]
+    fn direct_alpha_without_pad_byte() {
+        let image = decode_direct_with_components((2, 1, 32), (2, 8), 4, &[1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(*image.format(), PixelFormat::RGBA32);
+        assert_eq!(image.data(), [1, 2, 3, 0xFF, 4, 5, 6, 0xFF]);
+    }
+
+    #[test]
+    fn direct_planes_without_width() {
+        let raw = PictBuilder::new(1, 1)
+            .direct_bits((0, 1, 32), (4, 8), 3, &[2, 0, 0])
+            .build();
+
+        let ops = PICT::operations(raw.as_slice())
+            .expect("a preamble")
+            .collect::<Result<Vec<_>>>()
+            .expect("to parse");
+
+        match &ops[3].1 {
+            Operation::DirectBitsRect { pix_data, .. } => assert!(pix_data.is_empty()),
+            _ => panic!("not a DirectBitsRect"),
+        }
+    }
+
+    #[test]
+    fn bit_map() {
+        let raw = PictBuilder::new(3, 1)
+            .bits_rect(true, (3, 1, 1), &[], &[0b0100_0000, 0])
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.pixel(0, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(image.pixel(1, 0), [0, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn version_1_unaligned() {
+        let mut ops = vec![0x04, 0x01]; // TxFace, without filler
+        ops.push(0x31);
+        ops.extend(crate::tests::rect(0, 1, 1, 2));
+        let raw = crate::tests::version_1(2, 1, &ops);
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.data(), [0xFF, 0xFF, 0xFF, 0, 0, 0]);
+    }
+
+    #[test]
+    fn version_1_bit_map() {
+        let mut ops = vec![0x90];
+        ops.extend_from_slice(&2u16.to_be_bytes()); // row bytes
+        for _ in 0..3 {
+            ops.extend(crate::tests::rect(0, 0, 1, 3));
+        }
+        ops.extend_from_slice(&0u16.to_be_bytes()); // mode
+        ops.extend_from_slice(&[0b1010_0000, 0]);
+        let raw = crate::tests::version_1(3, 1, &ops);
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.pixel(0, 0), [0, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(1, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut raw = crate::tests::version_1(1, 1, &[]);
+        raw[512 + 2 + 8 + 1] = 3;
+
+        assert!(matches!(
+            PICT::parse(raw.as_slice()),
+            Err(Error::UnsupportedVersion(3))
+        ));
+    }
+
+    #[test]
+    fn composite_bands() {
+        let red = [0, 0xFF, 0, 0];
+        let blue = [0, 0, 0, 0xFF];
+        let raw = PictBuilder::new(2, 3)
+            .direct_bits_to(
+                (crate::tests::rect(0, 0, 1, 2), 0),
+                (2, 1, 32),
+                (1, 8),
+                3,
+                &[red, red].concat(),
+            )
+            .direct_bits_to(
+                (crate::tests::rect(1, 0, 3, 2), 0),
+                (1, 1, 32),
+                (1, 4),
+                3,
+                &blue,
+            )
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!((image.width(), image.height()), (2, 3));
+        assert_eq!(image.pixel(1, 0), [0xFF, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(0, 1), [0, 0, 0xFF, 0xFF]);
+        assert_eq!(image.pixel(1, 2), [0, 0, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn bitmap_over_drawing() {
+        let raw = PictBuilder::new(3, 1)
+            .op(0x0031, &crate::tests::rect(0, 0, 1, 3))
+            .direct_bits_to(
+                (crate::tests::rect(0, 1, 1, 2), 0),
+                (1, 1, 32),
+                (1, 4),
+                3,
+                &[0, 0xFF, 0xFF, 0xFF],
+            )
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.data(), [0, 0, 0, 0xFF, 0xFF, 0xFF, 0, 0, 0]);
+    }
+
+    #[test]
+    fn clipped_blend() {
+        let mut clip = 10u16.to_be_bytes().to_vec();
+        clip.extend(crate::tests::rect(0, 0, 1, 2));
+        let raw = PictBuilder::new(3, 1)
+            .op(0x0031, &crate::tests::rect(0, 0, 1, 3))
+            .op(0x001F, &[0x80, 0x00, 0x80, 0x00, 0x80, 0x00])
+            .op(0x0001, &clip)
+            .direct_bits_to(
+                (crate::tests::rect(0, 0, 1, 3), 32),
+                (3, 1, 32),
+                (1, 12),
+                3,
+                &[[0, 0xFF, 0xFF, 0xFF]; 3].concat(),
+            )
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.data(), [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0, 0, 0]);
+    }
+
+    #[test]
+    fn original_header() {
+        let raw = PictBuilder::original(2, 1)
+            .direct_bits_rect(2, 1, &[0, 0xFF, 0, 0, 0, 0, 0, 0xFF])
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.data(), [0xFF, 0, 0, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn unsupported_header_version() {
+        let mut raw = PictBuilder::new(1, 1).build();
+        raw[512 + 2 + 8 + 4 + 2 + 1] = 0xFD;
+
+        assert!(matches!(
+            PICT::parse(raw.as_slice()),
+            Err(Error::UnsupportedHeaderVersion(-3))
+        ));
+    }
+
+    #[test]
+    fn operations_with_offsets() {
+        let raw = PictBuilder::new(1, 1)
+            .op(0x00A1, &[0, 100, 0, 2, b'h', b'i'])
+            .direct_bits_rect(1, 1, &[0, 1, 2, 3])
+            .build();
+
+        let ops = PICT::operations(raw.as_slice())
+            .expect("a preamble")
+            .collect::<Result<Vec<_>>>()
+            .expect("to parse");
+
+        assert_eq!(
+            ops.iter()
+                .map(|(offset, op)| (*offset, op.opcode()))
+                .collect::<Vec<_>>(),
+            [
+                (522, Opcode::VersionOp),
+                (524, Opcode::Version),
+                (526, Opcode::HeaderOp),
+                (552, Opcode::LongComment),
+                (560, Opcode::DirectBitsRect),
+                (634, Opcode::OpEndPic),
+            ]
+        );
+        assert_eq!(ops[3].1.text(), Some("hi"));
+        match &ops[4].1 {
+            Operation::DirectBitsRect { pix_map, .. } => {
+                assert_eq!(
+                    (pix_map.bounds().width(), pix_map.bounds().height()),
+                    (1, 1)
+                );
+                assert_eq!((pix_map.pixel_size(), pix_map.components()), (32, (3, 8)));
+            }
+            _ => panic!("not a DirectBitsRect"),
+        }
+    }
+
+    #[test]
+    fn non_ascii_text() {
+        let raw = PictBuilder::new(1, 1)
+            .op(0x0028, &[0, 0, 0, 0, 2, 0xB9, b'A'])
+            .build();
+
+        let ops = PICT::operations(raw.as_slice())
+            .expect("a preamble")
+            .collect::<Result<Vec<_>>>()
+            .expect("to parse");
+
+        assert_eq!(ops[3].1.text(), Some("\u{B9}A"));
+    }
+
+    #[test]
+    fn missing_filler() {
+        let mut raw = PictBuilder::new(1, 1)
+            .compressed_quicktime(b"raw ", (1, 1, 24), (0, 0, 1, 1), b"abc")
+            .build();
+        // drop the filler and OpEndPic
+        raw.truncate(raw.len() - 3);
+
+        assert!(matches!(
+            PICT::parse(raw.as_slice()),
+            Err(Error::UnexpectedEOB)
+        ));
+    }
+
+    #[test]
+    fn operations_stop_at_error() {
+        let raw = PictBuilder::new(1, 1).op(0x00A1, &[0, 100, 0, 8]).build();
+
+        let mut ops = PICT::operations(raw.as_slice())
+            .expect("a preamble")
+            .skip(3);
+
+        assert!(matches!(ops.next(), Some(Err(Error::UnexpectedEOB))));
+        assert!(ops.next().is_none());
+    }
+
+    #[cfg(not(feature = "jpeg"))]
+    #[test]
+    fn keep_jpeg_without_feature() {
+        let raw = PictBuilder::new(4, 4)
+            .compressed_quicktime(b"jpeg", (4, 4, 24), (0, 0, 4, 4), b"raw")
+            .build();
+
+        let pict = PICT::parse(raw.as_slice()).expect("to parse");
+
+        assert!(matches!(&pict, PICT::JPEG(data) if data == b"raw"));
+        assert!(matches!(pict.to_image(), Err(Error::UnsupportedJPEG)));
     }
 }
@@ -5,80 +5,135 @@ use tracing::warn;
 use crate::{
     rectangle::Rectangle,
     utils::{ensure_remains_bytes, skip_reserved},
-    Result,
+    Error, Result,
 };
 
-#[derive(FromRepr)]
+#[derive(FromRepr, strum::Display)]
 #[repr(u16)]
 enum PixelType {
     Indexed = 0,
     Direct = 16,
 }
 
-#[allow(dead_code)]
-pub(crate) struct PixMap {
+/// Layout of the pixels of a bitmap, or a plain BitMap of 1 bit pixels
+pub struct PixMap {
     pub(crate) base_addr: u32,
     pub(crate) row_bytes: u16,
     pointed_is_pixmap_record: bool,
     pub(crate) bounds: Rectangle,
-    version: u16,
     pub(crate) pack_type: u16,
     pub(crate) pack_size: u32,
     horizontal_resolution: u32,
     vertical_resolution: u32,
     pixel_type: PixelType,
-    pixel_size: u16,
-    components_count: u16,
-    components_size: u16,
-    plane_offset: u32,
-    color_table_addr: u32,
+    pub(crate) pixel_size: u16,
+    pub(crate) components_count: u16,
+    pub(crate) components_size: u16,
 }
 
 impl PixMap {
+    pub(crate) const RAW_SIZE: usize = 50;
+
+    /// Size of a BitMap, before a PixMap's own fields
+    const BIT_MAP_SIZE: usize = 2 + 8;
+
+    /// PixMap prefixed by its base address, as in DirectBitsRect
     pub(crate) fn parse(buf: impl Buf) -> Result<Self> {
-        let mut buf = ensure_remains_bytes(buf, 46)?;
+        let mut buf = ensure_remains_bytes(buf, Self::RAW_SIZE)?;
 
         let base_addr = buf.get_u32();
         if base_addr % 4 != 0 {
             warn!("unoptimal PixMap base addr")
         }
 
+        let (row_bytes, pointed_is_pixmap_record, bounds) = Self::parse_bit_map(&mut buf)?;
+
+        Self::parse_fields(buf, base_addr, row_bytes, pointed_is_pixmap_record, bounds)
+    }
+
+    /// PixMap without its base address or a plain BitMap, as in BitsRect
+    pub(crate) fn parse_bits(mut buf: impl Buf) -> Result<Self> {
+        let (row_bytes, pointed_is_pixmap_record, bounds) = Self::parse_bit_map(&mut buf)?;
+        if !pointed_is_pixmap_record {
+            return Ok(Self::bit_map(row_bytes, bounds));
+        }
+
+        Self::parse_fields(buf, 0, row_bytes, pointed_is_pixmap_record, bounds)
+    }
+
+    /// One bit per pixel, without a color table
+    fn bit_map(row_bytes: u16, bounds: Rectangle) -> Self {
+        Self {
+            base_addr: 0,
+            row_bytes,
+            pointed_is_pixmap_record: false,
+            bounds,
+            pack_type: 0,
+            pack_size: 0,
+            horizontal_resolution: 72 << 16,
+            vertical_resolution: 72 << 16,
+            pixel_type: PixelType::Indexed,
+            pixel_size: 1,
+            components_count: 1,
+            components_size: 1,
+        }
+    }
+
+    /// Row bytes, if it is followed by the PixMap's fields, and bounds
+    fn parse_bit_map(buf: impl Buf) -> Result<(u16, bool, Rectangle)> {
+        let mut buf = ensure_remains_bytes(buf, Self::BIT_MAP_SIZE)?;
+
         let row_bytes_and_flag = buf.get_u16();
         let row_bytes = row_bytes_and_flag & 0x3fff;
-        if row_bytes % 2 != 0 || row_bytes >= 0x4000 {
-            panic!("invalid row bytes")
+        if !row_bytes.is_multiple_of(2) {
+            return Err(Error::InvalidRowBytes(row_bytes_and_flag));
         }
         if row_bytes % 4 != 0 {
             warn!("unoptimal row bytes")
         }
         let flags = row_bytes_and_flag >> 14;
         if flags & 0b01 != 0 {
-            panic!("unexpected row bytes 15th bit: {:b}", row_bytes_and_flag)
+            return Err(Error::InvalidRowBytes(row_bytes_and_flag));
         }
         let pointed_is_pixmap_record = flags == 0b10;
 
         let bounds = Rectangle::parse(&mut buf)?;
+
+        Ok((row_bytes, pointed_is_pixmap_record, bounds))
+    }
+
+    fn parse_fields(
+        buf: impl Buf,
+        base_addr: u32,
+        row_bytes: u16,
+        pointed_is_pixmap_record: bool,
+        bounds: Rectangle,
+    ) -> Result<Self> {
+        let mut buf = ensure_remains_bytes(buf, Self::RAW_SIZE - 4 - Self::BIT_MAP_SIZE)?;
+
         let version = buf.get_u16();
         if version != 0 {
-            panic!("unexpected version: {}", version)
+            return Err(Error::UnsupportedPixMapVersion(version));
         }
 
         let pack_type = buf.get_u16();
         if pack_type >= 5 {
-            panic!("unsupported pack_type: {}", pack_type)
+            return Err(Error::UnsupportedPackType(pack_type));
         }
         let pack_size = buf.get_u32();
         if pack_type == 0 && pack_size != 0 {
-            panic!("image not packed but pack size not zero: {}", pack_size)
+            return Err(Error::UnexpectedPackSize(pack_size));
         }
         let horizontal_resolution = buf.get_u32();
         let vertical_resolution = buf.get_u32();
-        let pixel_type = PixelType::from_repr(buf.get_u16()).expect("to be a valid pixel type");
+        let raw_pixel_type = buf.get_u16();
+        let pixel_type =
+            PixelType::from_repr(raw_pixel_type).ok_or(Error::InvalidPixelType(raw_pixel_type))?;
         let pixel_size = buf.get_u16();
         let components_count = buf.get_u16();
         let components_size = buf.get_u16();
-        let plane_offset = buf.get_u32();
-        let color_table_addr = buf.get_u32();
+        let _plane_offset = buf.get_u32();
+        let _color_table_addr = buf.get_u32();
         skip_reserved(buf, 4)?;
 
         Ok(Self {
@@ -86,7 +141,6 @@ impl PixMap {
             row_bytes,
             pointed_is_pixmap_record,
             bounds,
-            version,
             pack_type,
             pack_size,
             horizontal_resolution,
@@ -95,8 +149,68 @@ impl PixMap {
             pixel_size,
             components_count,
             components_size,
-            plane_offset,
-            color_table_addr,
         })
     }
+
+    /// Bounds of the pixels, in their own coordinates
+    pub fn bounds(&self) -> Rectangle {
+        self.bounds
+    }
+
+    /// Size of a row, padded to an even count
+    pub fn row_bytes(&self) -> u16 {
+        self.row_bytes
+    }
+
+    /// Packing scheme, with its packed size when stored packed
+    pub fn packing(&self) -> (u16, u32) {
+        (self.pack_type, self.pack_size)
+    }
+
+    /// Horizontal and vertical dots per inch
+    pub fn resolution(&self) -> (f64, f64) {
+        (
+            self.horizontal_resolution as f64 / 65536.0,
+            self.vertical_resolution as f64 / 65536.0,
+        )
+    }
+
+    /// Bits per pixel
+    pub fn pixel_size(&self) -> u16 {
+        self.pixel_size
+    }
+
+    /// Count of components of direct pixels, and their bits
+    pub fn components(&self) -> (u16, u16) {
+        (self.components_count, self.components_size)
+    }
+
+    /// Width and height of the bounds
+    pub fn size(&self) -> (usize, usize) {
+        (self.bounds.width(), self.bounds.height())
+    }
+
+    /// Whether direct pixels carry an alpha component, before the colors
+    pub fn has_alpha(&self) -> bool {
+        self.pixel_size == 32 && self.components_count == 4
+    }
+
+    /// Whether a color table follows, as BitMaps don't have one
+    pub fn is_pix_map(&self) -> bool {
+        self.pointed_is_pixmap_record
+    }
+
+    /// Human readable description of the fields, for debugging
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("bounds", self.bounds.to_string()),
+            ("row bytes", self.row_bytes.to_string()),
+            ("pack type", self.pack_type.to_string()),
+            ("pack size", self.pack_size.to_string()),
+            ("pixel type", self.pixel_type.to_string()),
+            ("pixel size", self.pixel_size.to_string()),
+            ("components count", self.components_count.to_string()),
+            ("components size", self.components_size.to_string()),
+        ]
+    }
 }
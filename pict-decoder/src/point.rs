@@ -1,11 +1,14 @@
+use std::fmt;
+
 use bytes::Buf;
 
 use crate::{utils::ensure_remains_bytes, Result};
 
-#[allow(dead_code)]
-pub(crate) struct Point {
-    pub(crate) x: u16,
-    pub(crate) y: u16,
+/// Stored vertical coordinate first
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point {
+    pub y: i16,
+    pub x: i16,
 }
 
 impl Point {
@@ -13,8 +16,14 @@ impl Point {
         let mut buf = ensure_remains_bytes(buf, 4)?;
 
         Ok(Self {
-            x: buf.get_u16(),
-            y: buf.get_u16(),
+            y: buf.get_i16(),
+            x: buf.get_i16(),
         })
     }
 }
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("({}, {})", self.x, self.y))
+    }
+}
@@ -1,7 +1,134 @@
 // https://developer.apple.com/library/archive/documentation/QuickTime/QTFF/QTFFChap1/qtff1.html
 
+mod blocks;
+mod cinepak;
 mod image_description;
+#[cfg(feature = "jpeg")]
+mod jpeg;
 mod matrix;
+mod palette;
+mod raw;
+mod rle;
+mod rpza;
+mod smc;
 
-pub(crate) use image_description::ImageDescription;
-pub(crate) use matrix::Matrix;
+use bytes::Buf;
+
+pub use image_description::ImageDescription;
+pub use matrix::Matrix;
+
+use crate::{
+    image::{Image, PixelFormat},
+    rectangle::Rectangle,
+    utils::ensure_remains_bytes,
+    Error, Result,
+};
+
+/// Accuracy from which images are smoothed when transformed
+pub(crate) const NORMAL_QUALITY: u32 = 0x200;
+
+fn read_u8(buf: &mut &[u8]) -> Result<u8> {
+    Ok(ensure_remains_bytes(buf, 1)?.get_u8())
+}
+
+fn read_u16(buf: &mut &[u8]) -> Result<u16> {
+    Ok(ensure_remains_bytes(buf, 2)?.get_u16())
+}
+
+fn read_u32(buf: &mut &[u8]) -> Result<u32> {
+    Ok(ensure_remains_bytes(buf, 4)?.get_u32())
+}
+
+/// Decode a compressed picture, cropped to the described size then to the source rectangle
+pub(crate) fn decode(
+    description: &ImageDescription,
+    source: &Rectangle,
+    data: &[u8],
+) -> Result<Image> {
+    let image = match &description.compressor_type {
+        b"raw " => raw::decode(description, data),
+        b"rle " => rle::decode(description, data),
+        b"rpza" => rpza::decode(description, data),
+        b"smc " => smc::decode(description, data),
+        b"cvid" => cinepak::decode(description, data),
+        #[cfg(feature = "jpeg")]
+        b"jpeg" => jpeg::decode(description, data),
+        #[cfg(not(feature = "jpeg"))]
+        b"jpeg" => Err(Error::UnsupportedJPEG),
+        codec => Err(Error::UnsupportedCodec(*codec)),
+    }?;
+
+    // codecs might pad to their blocks size
+    let width = (description.width as usize).min(image.width());
+    let height = (description.height as usize).min(image.height());
+
+    let top = (source.top.max(0) as usize).min(height);
+    let left = (source.left.max(0) as usize).min(width);
+    let bottom = (source.bottom.max(0) as usize).clamp(top, height);
+    let right = (source.right.max(0) as usize).clamp(left, width);
+    let cropped = image.crop(left, top, right - left, bottom - top);
+
+    Ok(match description.is_grayscale() {
+        true => cropped.convert(PixelFormat::Gray8),
+        false => cropped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::PictBuilder, Error, PICT};
+
+    #[test]
+    fn unknown_codec() {
+        let raw = PictBuilder::new(1, 1)
+            .compressed_quicktime(b"xyz ", (1, 1, 24), (0, 0, 1, 1), &[0; 3])
+            .build();
+
+        assert!(matches!(
+            PICT::parse(raw.as_slice()),
+            Err(Error::UnsupportedCodec(codec)) if codec == *b"xyz "
+        ));
+    }
+
+    #[test]
+    fn translate_and_scale() {
+        let matrix = [2 << 16, 0, 0, 0, 1 << 16, 0, 1 << 16, 1 << 16, 1 << 30];
+        let raw = PictBuilder::new(5, 2)
+            .transformed_quicktime(
+                b"raw ",
+                (2, 1, 8 + 32),
+                (0, 0, 1, 2),
+                (matrix, 0),
+                &[0xFF, 0],
+            )
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.row(0), [0xFF; 5 * 3]);
+        let grays: Vec<_> = (0..5).map(|x| image.pixel(x, 1)[0]).collect();
+        assert_eq!(grays, [0xFF, 0, 0, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn crop_to_source() {
+        let pixels: Vec<u8> = (0..4 * 3).collect();
+        let raw = PictBuilder::new(4, 3)
+            .compressed_quicktime(b"raw ", (4, 3, 8 + 32), (1, 1, 3, 9), &pixels)
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!((image.width(), image.height()), (4, 3));
+        assert_eq!(image.row(0), [0xFF; 4 * 3]);
+        assert_eq!(image.pixel(0, 1), [0xFF; 4]);
+        assert_eq!(
+            &image.row(1)[3..],
+            [250, 250, 250, 249, 249, 249, 248, 248, 248]
+        );
+    }
+}
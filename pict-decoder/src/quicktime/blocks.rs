@@ -0,0 +1,53 @@
+/// Top left corners of 4x4 blocks covering a picture, row by row
+pub(super) struct Blocks {
+    columns: usize,
+    rows: usize,
+    index: usize,
+}
+
+impl Blocks {
+    pub(super) fn new(width: u16, height: u16) -> Self {
+        Self {
+            columns: (width as usize).div_ceil(4),
+            rows: (height as usize).div_ceil(4),
+            index: 0,
+        }
+    }
+
+    /// Width of the picture padded to whole blocks
+    pub(super) fn width(&self) -> usize {
+        self.columns * 4
+    }
+
+    /// Height of the picture padded to whole blocks
+    pub(super) fn height(&self) -> usize {
+        self.rows * 4
+    }
+
+    /// Count of blocks already returned
+    pub(super) fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(super) fn is_done(&self) -> bool {
+        self.index >= self.columns * self.rows
+    }
+
+    /// Corner of the block at the given index
+    pub(super) fn corner(&self, index: usize) -> (usize, usize) {
+        ((index % self.columns) * 4, (index / self.columns) * 4)
+    }
+}
+
+impl Iterator for Blocks {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done() {
+            return None;
+        }
+
+        self.index += 1;
+        Some(self.corner(self.index - 1))
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{
+    image::{Image, PixelFormat},
+    Error, Result,
+};
+
+use super::{read_u16, read_u32, read_u8, ImageDescription};
+
+const CODEC: [u8; 4] = *b"cvid";
+
+/// Colors of 2x2 pixels, from top left to bottom right
+type Vector = [[u8; 3]; 4];
+
+#[derive(Clone)]
+struct Codebooks {
+    /// Each vector covers 4x4 pixels, doubling its pixels
+    v1: Vec<Vector>,
+    /// Four vectors cover 4x4 pixels
+    v4: Vec<Vector>,
+}
+
+impl Codebooks {
+    fn new() -> Self {
+        Self {
+            v1: vec![[[0; 3]; 4]; 256],
+            v4: vec![[[0; 3]; 4]; 256],
+        }
+    }
+}
+
+/// Split a chunk from the stream, with its id, its size counting its header
+fn read_chunk<'a>(buf: &mut &'a [u8]) -> Result<(u8, &'a [u8])> {
+    let header = read_u32(buf)?;
+    let size = ((header & 0x00FF_FFFF) as usize)
+        .checked_sub(4)
+        .ok_or(Error::CorruptedCodecData(CODEC))?;
+
+    // trust the data when truncated
+    let size = size.min(buf.len());
+    let (chunk, rest) = buf.split_at(size);
+    *buf = rest;
+
+    Ok(((header >> 24) as u8, chunk))
+}
+
+/// Update the codebook with either luma only or luma and chroma vectors, only for flagged entries
+fn decode_codebook(codebook: &mut [Vector], id: u8, mut buf: &[u8]) -> Result<()> {
+    let is_partial = id & 0x01 != 0;
+    let is_luma_only = id & 0x04 != 0;
+
+    let mut flags = 0u32;
+    let mut mask = 0u32;
+    for vector in codebook.iter_mut() {
+        if is_partial {
+            mask >>= 1;
+            if mask == 0 {
+                if buf.len() < 4 {
+                    break;
+                }
+                flags = read_u32(&mut buf)?;
+                mask = 0x8000_0000;
+            }
+            if flags & mask == 0 {
+                continue;
+            }
+        }
+
+        if buf.len() < if is_luma_only { 4 } else { 6 } {
+            break;
+        }
+        let mut luma = [0i32; 4];
+        for y in luma.iter_mut() {
+            *y = read_u8(&mut buf)? as i32;
+        }
+        let (u, v) = match is_luma_only {
+            true => (0, 0),
+            false => (
+                read_u8(&mut buf)? as i8 as i32,
+                read_u8(&mut buf)? as i8 as i32,
+            ),
+        };
+
+        for (pixel, y) in vector.iter_mut().zip(luma) {
+            *pixel = [y + v * 2, y - u / 2 - v, y + u * 2].map(|c| c.clamp(0, 255) as u8);
+        }
+    }
+
+    Ok(())
+}
+
+/// Paint blocks of the strip, reading in which codebook to look for each
+fn decode_vectors(
+    pixels: &mut [u8],
+    stride: usize,
+    codebooks: &Codebooks,
+    (top, left, bottom, right): (usize, usize, usize, usize),
+    id: u8,
+    mut buf: &[u8],
+) -> Result<()> {
+    let is_partial = id & 0x01 != 0;
+    let is_v1_only = id & 0x02 != 0;
+
+    let mut flags = 0u32;
+    let mut mask = 0u32;
+    let mut next_flag = |buf: &mut &[u8]| -> Result<bool> {
+        mask >>= 1;
+        if mask == 0 {
+            flags = read_u32(buf)?;
+            mask = 0x8000_0000;
+        }
+        Ok(flags & mask != 0)
+    };
+
+    for y in (top..bottom).step_by(4) {
+        for x in (left..right).step_by(4) {
+            if is_partial && !next_flag(&mut buf)? {
+                continue;
+            }
+
+            let quadrants = if is_v1_only || !next_flag(&mut buf)? {
+                let vector = &codebooks.v1[read_u8(&mut buf)? as usize];
+                // every color of the vector covers 2x2 pixels
+                [0, 1, 2, 3].map(|i| [vector[i]; 4])
+            } else {
+                let mut ret = [[[0; 3]; 4]; 4];
+                for quadrant in ret.iter_mut() {
+                    *quadrant = codebooks.v4[read_u8(&mut buf)? as usize];
+                }
+                ret
+            };
+
+            for (i, quadrant) in quadrants.iter().enumerate() {
+                let (qx, qy) = (x + (i % 2) * 2, y + (i / 2) * 2);
+                for (j, color) in quadrant.iter().enumerate() {
+                    let offset = ((qy + j / 2) * stride + qx + j % 2) * 3;
+                    pixels[offset..offset + 3].copy_from_slice(color);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Cinepak codec, horizontal strips of vector quantized 4x4 blocks
+pub(super) fn decode(description: &ImageDescription, data: &[u8]) -> Result<Image> {
+    let width = (description.width as usize).div_ceil(4) * 4;
+    let height = (description.height as usize).div_ceil(4) * 4;
+    let mut pixels = vec![0u8; width * height * 3];
+
+    let mut buf = data;
+    let flags = read_u8(&mut buf)?;
+    buf = buf.get(3 + 2 + 2..).ok_or(Error::UnexpectedEOB)?; // size, width and height
+    let strip_count = read_u16(&mut buf)?;
+
+    let mut codebooks = Codebooks::new();
+    let mut strip_top = 0;
+    for _ in 0..strip_count {
+        let (_, mut strip) = read_chunk(&mut buf)?;
+        let top_raw = read_u16(&mut strip)? as usize;
+        let left = read_u16(&mut strip)? as usize;
+        let bottom_raw = read_u16(&mut strip)? as usize;
+        let right = read_u16(&mut strip)? as usize;
+        // without a top, the strip is placed under the previous one
+        let (top, bottom) = match top_raw {
+            0 => (strip_top, strip_top + bottom_raw),
+            _ => (top_raw, bottom_raw),
+        };
+        if left >= right || top >= bottom {
+            return Err(Error::CorruptedCodecData(CODEC));
+        }
+        // the blocks, of 4x4 pixels, have to fit in the picture
+        let blocks_end = |start: usize, end: usize| start + (end - start).div_ceil(4) * 4;
+        if blocks_end(left, right) > width || blocks_end(top, bottom) > height {
+            return Err(Error::CorruptedCodecData(CODEC));
+        }
+
+        // strips either share codebooks or start anew
+        if flags & 0x01 != 0 {
+            codebooks = Codebooks::new();
+        }
+
+        while strip.len() >= 4 {
+            let (id, chunk) = read_chunk(&mut strip)?;
+            match id {
+                0x20 | 0x21 | 0x24 | 0x25 => decode_codebook(&mut codebooks.v4, id, chunk)?,
+                0x22 | 0x23 | 0x26 | 0x27 => decode_codebook(&mut codebooks.v1, id, chunk)?,
+                0x30..=0x32 => {
+                    decode_vectors(
+                        &mut pixels,
+                        width,
+                        &codebooks,
+                        (top, left, bottom, right),
+                        id,
+                        chunk,
+                    )?;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        strip_top = bottom;
+    }
+
+    Image::new(width, height, PixelFormat::RGB24, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::PictBuilder, PICT};
+
+    use super::*;
+
+    fn chunk(id: u8, content: &[u8]) -> Vec<u8> {
+        let mut ret = ((id as u32) << 24 | (content.len() as u32 + 4))
+            .to_be_bytes()
+            .to_vec();
+        ret.extend_from_slice(content);
+        ret
+    }
+
+    /// Frame of a single strip covering the picture
+    fn frame(width: u16, height: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        strip_frame((width, height), (0, 0, height, width), chunks)
+    }
+
+    /// Frame of a single strip at the given top, left, bottom and right
+    fn strip_frame(
+        (width, height): (u16, u16),
+        bounds: (u16, u16, u16, u16),
+        chunks: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut strip = Vec::new();
+        for value in [bounds.0, bounds.1, bounds.2, bounds.3] {
+            strip.extend_from_slice(&value.to_be_bytes());
+        }
+        strip.extend(chunks.concat());
+        let strip = chunk(0x10, &strip);
+
+        let mut ret = ((strip.len() as u32) + 10).to_be_bytes().to_vec();
+        ret.extend_from_slice(&width.to_be_bytes());
+        ret.extend_from_slice(&height.to_be_bytes());
+        ret.extend_from_slice(&1u16.to_be_bytes());
+        ret.extend(strip);
+        ret
+    }
+
+    fn decode(width: u16, height: u16, data: &[u8]) -> Image {
+        let raw = PictBuilder::new(width, height)
+            .compressed_quicktime(b"cvid", (width, height, 24), (0, 0, height, width), data)
+            .build();
+
+        PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode")
+    }
+
+    #[test]
+    fn unaligned_strip() {
+        let v1 = chunk(0x26, &[10, 20, 30, 40]);
+        let vectors = chunk(0x32, &[0]);
+        let data = strip_frame((4, 4), (2, 0, 4, 4), &[v1, vectors]);
+        let raw = PictBuilder::new(4, 4)
+            .compressed_quicktime(b"cvid", (4, 4, 24), (0, 0, 4, 4), &data)
+            .build();
+
+        assert!(matches!(
+            PICT::parse(raw.as_slice()).and_then(|pict| pict.to_image()),
+            Err(Error::CorruptedCodecData(_))
+        ));
+    }
+
+    #[test]
+    fn chroma_conversion() {
+        let mut codebook = vec![[[0; 3]; 4]; 1];
+
+        decode_codebook(
+            &mut codebook,
+            0x22,
+            &[100, 100, 100, 100, 10, (-10i8) as u8],
+        )
+        .unwrap();
+
+        assert_eq!(codebook[0][0], [80, 105, 120]);
+    }
+
+    #[test]
+    fn v1_and_v4_blocks() {
+        // v1 entry 0 is a gradient of gray, v4 entry 0 is black and 1 white
+        let v1 = chunk(0x26, &[10, 20, 30, 40]);
+        let v4 = chunk(0x24, &[0, 0, 0, 0, 255, 255, 255, 255]);
+        // first block in v1, second in v4
+        let mut vectors = 0x4000_0000u32.to_be_bytes().to_vec();
+        vectors.extend_from_slice(&[0, 0, 1, 1, 0]);
+        let vectors = chunk(0x30, &vectors);
+
+        let image = decode(8, 4, &frame(8, 4, &[v1, v4, vectors]));
+
+        assert_eq!(image.pixel(1, 1), [10, 10, 10, 0xFF]);
+        assert_eq!(image.pixel(2, 1), [20, 20, 20, 0xFF]);
+        assert_eq!(image.pixel(3, 3), [40, 40, 40, 0xFF]);
+        assert_eq!(image.pixel(4, 0), [0, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(6, 1), [255, 255, 255, 0xFF]);
+        assert_eq!(image.pixel(4, 2), [255, 255, 255, 0xFF]);
+        assert_eq!(image.pixel(7, 3), [0, 0, 0, 0xFF]);
+    }
+}
@@ -1,9 +1,11 @@
+use std::cmp;
+
 use bytes::Buf;
 use tracing::warn;
 
 use crate::{
     utils::{ensure_remains_bytes, skip_reserved},
-    Result,
+    Error, Result,
 };
 
 fn read_4_bytes(buf: impl Buf) -> Result<[u8; 4]> {
@@ -15,23 +17,18 @@ fn read_4_bytes(buf: impl Buf) -> Result<[u8; 4]> {
     Ok(buffer)
 }
 
-#[allow(dead_code)]
-pub(crate) struct ImageDescription {
-    compressor_type: [u8; 4],
-    version: u16,
-    revision: u16,
+/// Format of the compressed image of a CompressedQuickTime
+pub struct ImageDescription {
+    pub(crate) compressor_type: [u8; 4],
     vendor: [u8; 4],
-    temporal_quality: u32,
-    spatial_quality: u32,
-    width: u16,
-    height: u16,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
     horizontal_resolution: u32,
     vertical_resolution: u32,
     pub(crate) data_size: u32,
     frame_count: u16,
     name: String,
-    depth: u16,
-    color_table_id: u16,
+    pub(crate) depth: u16,
 }
 
 impl ImageDescription {
@@ -42,15 +39,15 @@ impl ImageDescription {
 
         let struct_size = buf.get_u32();
         if struct_size as usize != Self::RAW_SIZE {
-            panic!("unexpected struct size: {}", struct_size)
+            return Err(Error::UnexpectedImageDescriptionSize(struct_size));
         }
         let compressor_type = read_4_bytes(&mut buf)?;
         skip_reserved(&mut buf, 8)?;
-        let version = buf.get_u16();
-        let revision = buf.get_u16();
+        let _version = buf.get_u16();
+        let _revision = buf.get_u16();
         let vendor = read_4_bytes(&mut buf)?;
-        let temporal_quality = buf.get_u32();
-        let spatial_quality = buf.get_u32();
+        let _temporal_quality = buf.get_u32();
+        let _spatial_quality = buf.get_u32();
         let width = buf.get_u16();
         let height = buf.get_u16();
         let horizontal_resolution = buf.get_u32();
@@ -62,22 +59,18 @@ impl ImageDescription {
         let name_size = buf.get_u8();
         let mut raw = vec![0; 31]; // 31 bytes every time
         buf.copy_to_slice(&mut raw);
-        let rem = raw.split_off(name_size as usize);
+        let rem = raw.split_off(cmp::min(name_size as usize, raw.len()));
         if rem.into_iter().any(|c| c != 0) {
             warn!("got data after string's end")
         }
         let name = String::from_utf8(raw)?;
 
         let depth = buf.get_u16();
-        let color_table_id = buf.get_u16();
+        let _color_table_id = buf.get_u16();
 
         Ok(Self {
             compressor_type,
-            version,
-            revision,
             vendor,
-            temporal_quality,
-            spatial_quality,
             width,
             height,
             horizontal_resolution,
@@ -86,7 +79,63 @@ impl ImageDescription {
             frame_count,
             name,
             depth,
-            color_table_id,
         })
     }
+
+    /// Four characters code of the codec
+    pub fn codec(&self) -> [u8; 4] {
+        self.compressor_type
+    }
+
+    /// Width and height of the decoded image
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Bits per pixel, or 32 plus the bits of gray
+    pub fn depth(&self) -> u16 {
+        self.depth
+    }
+
+    /// Four characters code of the compressor's developer
+    pub fn vendor(&self) -> [u8; 4] {
+        self.vendor
+    }
+
+    /// Horizontal and vertical dots per inch
+    pub fn resolution(&self) -> (f64, f64) {
+        (
+            self.horizontal_resolution as f64 / 65536.0,
+            self.vertical_resolution as f64 / 65536.0,
+        )
+    }
+
+    pub fn frame_count(&self) -> u16 {
+        self.frame_count
+    }
+
+    /// Name of the compressor
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Depths above 32 are grayscale
+    pub(crate) fn is_grayscale(&self) -> bool {
+        self.depth > 32
+    }
+
+    /// Human readable description of the fields, for debugging
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "codec",
+                String::from_utf8_lossy(&self.compressor_type).into_owned(),
+            ),
+            ("width", self.width.to_string()),
+            ("height", self.height.to_string()),
+            ("depth", self.depth.to_string()),
+            ("data size", self.data_size.to_string()),
+            ("name", format!("{:?}", self.name)),
+        ]
+    }
 }
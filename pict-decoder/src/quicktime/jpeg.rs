@@ -0,0 +1,102 @@
+use jpeg_decoder::{Decoder, PixelFormat as JPEGPixelFormat};
+
+use crate::{
+    image::{Image, PixelFormat},
+    Error, Result,
+};
+
+use super::ImageDescription;
+
+/// Decode a JPEG, at least as big as described
+pub(super) fn decode(description: &ImageDescription, data: &[u8]) -> Result<Image> {
+    let mut decoder = Decoder::new(data);
+    let pixels = decoder.decode()?;
+    let info = decoder.info().ok_or(Error::UnexpectedEOB)?;
+
+    let format = match info.pixel_format {
+        JPEGPixelFormat::L8 => PixelFormat::Gray8,
+        JPEGPixelFormat::RGB24 => PixelFormat::RGB24,
+        pixel_format => {
+            return Err(Error::UnsupportedJPEGPixelFormat(format!(
+                "{:?}",
+                pixel_format
+            )))
+        }
+    };
+    let (width, height) = (info.width as usize, info.height as usize);
+    let image = Image::new(width, height, format, pixels)?;
+
+    // JPEG might be padded to its blocks size
+    let (described_width, described_height) =
+        (description.width as usize, description.height as usize);
+    if described_width > width || described_height > height {
+        return Err(Error::UnexpectedJPEGSize(info.width, info.height));
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use jpeg_encoder::{ColorType, Encoder};
+
+    use crate::{tests::PictBuilder, PixelFormat, PICT};
+
+    fn encode(width: u16, height: u16, pixels: &[u8], color_type: ColorType) -> Vec<u8> {
+        let mut ret = Vec::new();
+        Encoder::new(&mut ret, 100)
+            .encode(pixels, width, height, color_type)
+            .expect("to encode JPEG");
+
+        ret
+    }
+
+    #[test]
+    fn decode_cropped_to_source() {
+        let pixels: Vec<u8> = (0..16 * 8).flat_map(|_| [250, 10, 10]).collect();
+        let jpeg = encode(16, 8, &pixels, ColorType::Rgb);
+
+        let raw = PictBuilder::new(16, 8)
+            .compressed_quicktime(b"jpeg", (16, 8, 24), (2, 1, 6, 13), &jpeg)
+            .build();
+
+        let PICT::Image(image) = PICT::parse(raw.as_slice()).expect("to parse") else {
+            panic!("not decoded");
+        };
+        assert_eq!((image.width(), image.height()), (16, 8));
+        assert_eq!(*image.format(), PixelFormat::RGB24);
+        let [r, g, b, _] = image.pixel(5, 2);
+        assert!(r > 240 && g < 20 && b < 20, "{:?}", (r, g, b));
+        assert_eq!(image.pixel(0, 1), [0xFF; 4]);
+        assert_eq!(image.pixel(13, 6), [0xFF; 4]);
+    }
+
+    #[test]
+    fn grayscale_depth() {
+        let jpeg = encode(4, 4, &[100; 4 * 4 * 3], ColorType::Rgb);
+
+        let raw = PictBuilder::new(4, 4)
+            .compressed_quicktime(b"jpeg", (4, 4, 40), (0, 0, 4, 4), &jpeg)
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+        assert_eq!(*image.format(), PixelFormat::Gray8);
+        assert!(image.data().iter().all(|&v| v.abs_diff(100) <= 2));
+    }
+
+    #[test]
+    fn bigger_than_data() {
+        let jpeg = encode(4, 4, &[0; 4 * 4], ColorType::Luma);
+
+        let raw = PictBuilder::new(8, 8)
+            .compressed_quicktime(b"jpeg", (8, 8, 40), (0, 0, 8, 8), &jpeg)
+            .build();
+
+        assert!(matches!(
+            PICT::parse(raw.as_slice()),
+            Err(crate::Error::UnexpectedJPEGSize(4, 4))
+        ));
+    }
+}
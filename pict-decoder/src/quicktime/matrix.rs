@@ -1,20 +1,211 @@
+use std::fmt;
+
 use bytes::Buf;
 
-use crate::{utils::ensure_remains_bytes, Result};
+use crate::{
+    image::{Image, PixelFormat},
+    rectangle::Rectangle,
+    utils::ensure_remains_bytes,
+    Result,
+};
 
-pub(crate) struct Matrix([[u32; 3]; 3]);
+/// Mapping of the image to the picture, as rows of a, b, u then c, d, v then tx, ty, w
+pub struct Matrix([[f64; 3]; 3]);
 
 impl Matrix {
+    /// 16.16 fixed point for the first two columns, 2.30 for the last
     pub(crate) fn parse(buf: impl Buf) -> Result<Self> {
         let mut buf = ensure_remains_bytes(buf, 36)?;
-        let mut matrix = [[0u32; 3]; 3];
+        let mut matrix = [[0f64; 3]; 3];
 
         for line in matrix.iter_mut() {
-            for i in line.iter_mut() {
-                *i = buf.get_u32();
-            }
+            let [x, y, projective] = line;
+            *x = buf.get_i32() as f64 / (1 << 16) as f64;
+            *y = buf.get_i32() as f64 / (1 << 16) as f64;
+            *projective = buf.get_i32() as f64 / (1 << 30) as f64;
         }
 
         Ok(Self(matrix))
     }
+
+    /// Integer offset, if it only moves the image
+    fn translation(&self) -> Option<(i32, i32)> {
+        let [[a, b, u], [c, d, v], [tx, ty, w]] = self.0;
+        let is_integer = tx.fract() == 0. && ty.fract() == 0.;
+
+        ([a, b, u, c, d, v, w] == [1., 0., 0., 0., 1., 0., 1.] && is_integer)
+            .then_some((tx as i32, ty as i32))
+    }
+
+    /// Picture coordinates of the point of the image
+    fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [[a, b, u], [c, d, v], [tx, ty, w]] = self.0;
+        let divisor = x * u + y * v + w;
+
+        (
+            (x * a + y * c + tx) / divisor,
+            (x * b + y * d + ty) / divisor,
+        )
+    }
+
+    /// Mapping back from the picture to the image, if not degenerated
+    fn inverse(&self) -> Option<Self> {
+        let m = self.0;
+        let minor = |row: usize, column: usize| {
+            let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+            let (c0, c1) = ((column + 1) % 3, (column + 2) % 3);
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+
+        let determinant = (0..3).map(|i| m[0][i] * minor(0, i)).sum::<f64>();
+        if determinant.abs() < f64::EPSILON {
+            return None;
+        }
+
+        let mut ret = [[0f64; 3]; 3];
+        for (row, line) in ret.iter_mut().enumerate() {
+            for (column, value) in line.iter_mut().enumerate() {
+                *value = minor(column, row) / determinant;
+            }
+        }
+
+        Some(Self(ret))
+    }
+
+    /// Image drawn in the picture with its bounds, from the part of the image at the given
+    /// position, smoothed bilinearly or taking the nearest pixels
+    pub(crate) fn place(
+        &self,
+        image: Image,
+        (left, top): (i16, i16),
+        smooth: bool,
+    ) -> (Image, Rectangle) {
+        let (width, height) = (image.width(), image.height());
+
+        if let Some((dx, dy)) = self.translation() {
+            let (left, top) = (left as i32 + dx, top as i32 + dy);
+            let bounds = Rectangle {
+                top: clamp(top),
+                left: clamp(left),
+                bottom: clamp(top + height as i32),
+                right: clamp(left + width as i32),
+            };
+            return (image, bounds);
+        }
+
+        let (x0, y0) = (left as f64, top as f64);
+        let (x1, y1) = (x0 + width as f64, y0 + height as f64);
+        let corners = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)].map(|(x, y)| self.apply(x, y));
+        let bound = |pick: fn(&(f64, f64)) -> f64, fold: fn(f64, f64) -> f64, start: f64| {
+            clamp(corners.iter().map(pick).fold(start, fold) as i32)
+        };
+        let bounds = Rectangle {
+            top: bound(|c| c.1.floor(), f64::min, f64::MAX),
+            left: bound(|c| c.0.floor(), f64::min, f64::MAX),
+            bottom: bound(|c| c.1.ceil(), f64::max, f64::MIN),
+            right: bound(|c| c.0.ceil(), f64::max, f64::MIN),
+        };
+
+        let (placed_width, placed_height) = (bounds.width(), bounds.height());
+        let mut data = vec![0; placed_width * placed_height * 4];
+        if let Some(inverse) = self.inverse().filter(|_| width > 0 && height > 0) {
+            for (i, pixel) in data.chunks_exact_mut(4).enumerate() {
+                let x = bounds.left as f64 + (i % placed_width) as f64 + 0.5;
+                let y = bounds.top as f64 + (i / placed_width) as f64 + 0.5;
+                let (x, y) = inverse.apply(x, y);
+                let (x, y) = (x - x0, y - y0);
+                if !(0. ..width as f64).contains(&x) || !(0. ..height as f64).contains(&y) {
+                    continue;
+                }
+
+                pixel.copy_from_slice(&match smooth {
+                    true => bilinear(&image, x - 0.5, y - 0.5),
+                    false => image.pixel(x as usize, y as usize),
+                });
+            }
+        }
+
+        let placed = Image::new(placed_width, placed_height, PixelFormat::RGBA32, data)
+            .expect("data to be of the image's size");
+
+        (placed, bounds)
+    }
+}
+
+fn clamp(value: i32) -> i16 {
+    value.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+/// Color between the four nearest pixels, the position being from the first pixel's center
+fn bilinear(image: &Image, x: f64, y: f64) -> [u8; 4] {
+    let (max_x, max_y) = (image.width() - 1, image.height() - 1);
+    let (left, top) = (x.floor().max(0.) as usize, y.floor().max(0.) as usize);
+    let (left, top) = (left.min(max_x), top.min(max_y));
+    let (right, bottom) = ((left + 1).min(max_x), (top + 1).min(max_y));
+    let (fx, fy) = (
+        (x - left as f64).clamp(0., 1.),
+        (y - top as f64).clamp(0., 1.),
+    );
+
+    let [top_left, top_right, bottom_left, bottom_right] = [
+        image.pixel(left, top),
+        image.pixel(right, top),
+        image.pixel(left, bottom),
+        image.pixel(right, bottom),
+    ];
+
+    [0, 1, 2, 3].map(|i| {
+        let upper = top_left[i] as f64 * (1. - fx) + top_right[i] as f64 * fx;
+        let lower = bottom_left[i] as f64 * (1. - fx) + bottom_right[i] as f64 * fx;
+        (upper * (1. - fy) + lower * fy).round() as u8
+    })
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            f.write_fmt(format_args!("[{} {} {}]", line[0], line[1], line[2]))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(values: [i32; 9]) -> Matrix {
+        let raw: Vec<u8> = values.into_iter().flat_map(i32::to_be_bytes).collect();
+        Matrix::parse(raw.as_slice()).expect("to parse")
+    }
+
+    const ONE: i32 = 1 << 16;
+    const W: i32 = 1 << 30;
+
+    #[test]
+    fn fixed_point_formats() {
+        let matrix = matrix([2 * ONE, 0, 0, 0, ONE / 2, 0, 3 * ONE, -ONE, W]);
+
+        assert_eq!(matrix.to_string(), "[2 0 0] [0 0.5 0] [3 -1 1]");
+        assert_eq!(matrix.apply(1., 2.), (5., 0.));
+    }
+
+    #[test]
+    fn scale_nearest_and_bilinear() {
+        let image = Image::new(2, 1, PixelFormat::Gray8, vec![0, 0xFF]).expect("an image");
+        let matrix = matrix([2 * ONE, 0, 0, 0, ONE, 0, ONE, 0, W]);
+
+        let (nearest, bounds) = matrix.place(image.clone(), (0, 0), false);
+        assert_eq!((bounds.left, bounds.right, bounds.bottom), (1, 5, 1));
+        let reds: Vec<_> = (0..4).map(|x| nearest.pixel(x, 0)[0]).collect();
+        assert_eq!(reds, [0, 0, 0xFF, 0xFF]);
+
+        let (smooth, _) = matrix.place(image, (0, 0), true);
+        let reds: Vec<_> = (0..4).map(|x| smooth.pixel(x, 0)[0]).collect();
+        assert_eq!(reds, [0, 0x40, 0xBF, 0xFF]);
+    }
 }
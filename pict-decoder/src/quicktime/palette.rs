@@ -0,0 +1,90 @@
+/// Standard Macintosh palette of 16 colors
+const MAC_16: [[u8; 3]; 16] = [
+    [0xFF, 0xFF, 0xFF],
+    [0xFC, 0xF3, 0x05],
+    [0xFF, 0x64, 0x02],
+    [0xDD, 0x08, 0x06],
+    [0xF2, 0x08, 0x84],
+    [0x46, 0x00, 0xA5],
+    [0x00, 0x00, 0xD4],
+    [0x02, 0xAB, 0xEA],
+    [0x1F, 0xB7, 0x14],
+    [0x00, 0x64, 0x11],
+    [0x56, 0x2C, 0x05],
+    [0x90, 0x71, 0x3A],
+    [0xC0, 0xC0, 0xC0],
+    [0x80, 0x80, 0x80],
+    [0x40, 0x40, 0x40],
+    [0x00, 0x00, 0x00],
+];
+
+/// Standard Macintosh palette of 256 colors: a color cube then ramps of red, green, blue and gray
+fn mac_256() -> Vec<[u8; 3]> {
+    const CUBE: [u8; 6] = [0xFF, 0xCC, 0x99, 0x66, 0x33, 0x00];
+    const RAMP: [u8; 10] = [0xEE, 0xDD, 0xBB, 0xAA, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+
+    let mut ret = Vec::with_capacity(256);
+    for r in CUBE {
+        for g in CUBE {
+            for b in CUBE {
+                ret.push([r, g, b]);
+            }
+        }
+    }
+    ret.pop(); // black comes last
+
+    for i in 0..3 {
+        ret.extend(RAMP.iter().map(|&v| {
+            let mut color = [0; 3];
+            color[i] = v;
+            color
+        }));
+    }
+    ret.extend(RAMP.iter().map(|&v| [v; 3]));
+    ret.push([0; 3]);
+
+    ret
+}
+
+/// Gray levels from white to black
+fn gray(bits: u16) -> Vec<[u8; 3]> {
+    let count = 1usize << bits;
+
+    (0..count)
+        .map(|i| [(255 - i * 255 / (count - 1)) as u8; 3])
+        .collect()
+}
+
+/// Palette used by QuickTime when an indexed picture doesn't embed its own
+pub(super) fn default(depth: u16) -> Option<Vec<[u8; 3]>> {
+    Some(match depth {
+        1 => gray(1),
+        2 => vec![[0xFF; 3], [0xAC; 3], [0x55; 3], [0x00; 3]],
+        4 => MAC_16.to_vec(),
+        8 => mac_256(),
+        33 | 34 | 36 | 40 => gray(depth - 32),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mac_256_layout() {
+        let palette = mac_256();
+
+        assert_eq!(palette.len(), 256);
+        assert_eq!(palette[0], [0xFF; 3]);
+        assert_eq!(palette[214], [0x00, 0x00, 0x33]);
+        assert_eq!(palette[215], [0xEE, 0x00, 0x00]);
+        assert_eq!(palette[245], [0xEE; 3]);
+        assert_eq!(palette[255], [0x00; 3]);
+    }
+
+    #[test]
+    fn gray_from_white() {
+        assert_eq!(gray(2), [[255; 3], [170; 3], [85; 3], [0; 3]]);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    image::{Image, PixelFormat},
+    Error, Result,
+};
+
+use super::{palette, ImageDescription};
+
+const CODEC: [u8; 4] = *b"raw ";
+
+/// Bits per pixel of the depth, grayscale depths are offset by 32
+pub(super) fn bits_per_pixel(depth: u16) -> u16 {
+    match depth {
+        33..=40 => depth - 32,
+        _ => depth,
+    }
+}
+
+/// Bytes of a row without padding
+pub(super) fn row_size(width: usize, depth: u16) -> usize {
+    (width * bits_per_pixel(depth) as usize).div_ceil(8)
+}
+
+/// Convert rows of packed pixels, as stored by QuickTime, to an image
+pub(super) fn unpack(
+    codec: [u8; 4],
+    width: usize,
+    height: usize,
+    depth: u16,
+    stride: usize,
+    data: Vec<u8>,
+) -> Result<Image> {
+    match bits_per_pixel(depth) {
+        bits @ (1 | 2 | 4 | 8) => {
+            let palette =
+                palette::default(depth).ok_or(Error::UnsupportedCodecDepth(codec, depth))?;
+
+            Image::from_indexes(width, height, stride, bits, palette, data)
+        }
+        16 => Image::with_stride(width, height, stride, PixelFormat::RGB555, data),
+        24 => Image::with_stride(width, height, stride, PixelFormat::RGB24, data),
+        32 => {
+            // alpha is left unused by QuickTime
+            let argb = Image::with_stride(width, height, stride, PixelFormat::RGBA32, data)?;
+            let rgb = (0..height)
+                .flat_map(|y| argb.row(y).chunks_exact(4))
+                .flat_map(|pixel| [pixel[1], pixel[2], pixel[3]])
+                .collect();
+
+            Image::new(width, height, PixelFormat::RGB24, rgb)
+        }
+        _ => Err(Error::UnsupportedCodecDepth(codec, depth)),
+    }
+}
+
+/// Uncompressed rows, possibly padded
+pub(super) fn decode(description: &ImageDescription, data: &[u8]) -> Result<Image> {
+    let (width, height) = (description.width as usize, description.height as usize);
+
+    let stride = match height {
+        0 => 0,
+        _ => data.len() / height,
+    };
+    if stride < row_size(width, description.depth) {
+        return Err(Error::UnexpectedImageSize(data.len()));
+    }
+
+    unpack(
+        CODEC,
+        width,
+        height,
+        description.depth,
+        stride,
+        data.to_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::PictBuilder, PixelFormat, PICT};
+
+    fn parse(width: u16, height: u16, depth: u16, data: &[u8]) -> crate::Result<PICT> {
+        let raw = PictBuilder::new(width, height)
+            .compressed_quicktime(b"raw ", (width, height, depth), (0, 0, height, width), data)
+            .build();
+
+        PICT::parse(raw.as_slice())
+    }
+
+    #[test]
+    fn padded_rgb() {
+        let image = parse(1, 2, 24, &[1, 2, 3, 0, 4, 5, 6, 0])
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(*image.format(), PixelFormat::RGB24);
+        assert_eq!(image.data(), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn argb_drops_alpha() {
+        let image = parse(2, 1, 32, &[0, 1, 2, 3, 0xFF, 4, 5, 6])
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.data(), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn one_bit_is_black_on_white() {
+        let image = parse(3, 1, 1, &[0b0100_0000, 0])
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(image.pixel(0, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(image.pixel(1, 0), [0, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn grayscale_is_inverted() {
+        let image = parse(2, 1, 40, &[0, 0xFF])
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+
+        assert_eq!(*image.format(), PixelFormat::Gray8);
+        assert_eq!(image.data(), [0xFF, 0]);
+    }
+}
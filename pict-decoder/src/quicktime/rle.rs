@@ -0,0 +1,162 @@
+use crate::{image::Image, Error, Result};
+
+use super::{raw, read_u16, read_u32, read_u8, ImageDescription};
+
+const CODEC: [u8; 4] = *b"rle ";
+
+/// Animation codec, run lengths of pixels or of groups of indexed pixels
+pub(super) fn decode(description: &ImageDescription, data: &[u8]) -> Result<Image> {
+    let depth = description.depth;
+    let unit = match raw::bits_per_pixel(depth) {
+        2 | 4 | 8 => 4,
+        16 => 2,
+        24 => 3,
+        32 => 4,
+        _ => return Err(Error::UnsupportedCodecDepth(CODEC, depth)),
+    };
+    let (width, height) = (description.width as usize, description.height as usize);
+    let stride = raw::row_size(width, depth).div_ceil(unit) * unit;
+    let mut pixels = vec![0; stride * height];
+
+    let mut buf = data;
+    let chunk_size = read_u32(&mut buf)? as usize;
+    // smaller chunks are unchanged frames
+    if chunk_size >= 8 {
+        let header = read_u16(&mut buf)?;
+        let (start_line, line_count) = match header & 0x0008 {
+            0 => (0, height),
+            _ => {
+                let start_line = read_u16(&mut buf)? as usize;
+                read_u16(&mut buf)?;
+                let line_count = read_u16(&mut buf)? as usize;
+                read_u16(&mut buf)?;
+                (start_line, line_count)
+            }
+        };
+
+        for line in start_line..start_line + line_count {
+            let row = pixels
+                .get_mut(line * stride..(line + 1) * stride)
+                .ok_or(Error::CorruptedCodecData(CODEC))?;
+            decode_line(&mut buf, row, unit)?;
+        }
+    }
+
+    raw::unpack(CODEC, width, height, depth, stride, pixels)
+}
+
+fn decode_line(buf: &mut &[u8], row: &mut [u8], unit: usize) -> Result<()> {
+    let corrupted = || Error::CorruptedCodecData(CODEC);
+    let skip = |buf: &mut &[u8], x: usize| -> Result<usize> {
+        (x + unit * read_u8(buf)? as usize)
+            .checked_sub(unit)
+            .ok_or_else(corrupted)
+    };
+
+    let mut x = skip(buf, 0)?;
+    loop {
+        match read_u8(buf)? as i8 {
+            -1 => return Ok(()),
+            0 => x = skip(buf, x)?,
+            code if code < 0 => {
+                let value = buf.get(..unit).ok_or(Error::UnexpectedEOB)?;
+                *buf = &buf[unit..];
+                for _ in 0..code.unsigned_abs() {
+                    row.get_mut(x..x + unit)
+                        .ok_or_else(corrupted)?
+                        .copy_from_slice(value);
+                    x += unit;
+                }
+            }
+            code => {
+                let size = code as usize * unit;
+                let values = buf.get(..size).ok_or(Error::UnexpectedEOB)?;
+                *buf = &buf[size..];
+                row.get_mut(x..x + size)
+                    .ok_or_else(corrupted)?
+                    .copy_from_slice(values);
+                x += size;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::PictBuilder, PixelFormat, PICT};
+
+    fn decode(width: u16, height: u16, depth: u16, data: &[u8]) -> crate::Image {
+        let raw = PictBuilder::new(width, height)
+            .compressed_quicktime(b"rle ", (width, height, depth), (0, 0, height, width), data)
+            .build();
+
+        PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode")
+    }
+
+    #[test]
+    fn runs_and_copies() {
+        #[rustfmt::skip]
+        let data = [
+            0, 0, 0, 0, // chunk size
+            0x00, 0x08, // header with lines
+            0, 1, 0, 0, 0, 1, 0, 0, // second line only
+            2, // skip first pixel
+            (-2i8) as u8, 0xFF, 0x00, 0x00, // two red
+            1, 0x00, 0xFF, 0x00, // one green
+            0xFF,
+        ];
+        let mut data = data.to_vec();
+        let size = data.len() as u32;
+        data[..4].copy_from_slice(&size.to_be_bytes());
+
+        let image = decode(4, 2, 24, &data);
+
+        assert_eq!(*image.format(), PixelFormat::RGB24);
+        assert_eq!(image.row(0), [0; 12]);
+        assert_eq!(image.row(1), [0, 0, 0, 0xFF, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0]);
+    }
+
+    #[test]
+    fn longest_run() {
+        #[rustfmt::skip]
+        let data = [
+            0, 0, 0, 18,
+            0x00, 0x00,
+            1, 0x80, 0x00, 0x00, 0xFF, // 128 blue
+            0xFF,
+        ];
+
+        let image = decode(128, 1, 24, &data);
+
+        assert!(image.row(0).chunks(3).all(|pixel| pixel == [0, 0, 0xFF]));
+    }
+
+    #[test]
+    fn indexed_groups() {
+        #[rustfmt::skip]
+        let data = [
+            0, 0, 0, 12,
+            0x00, 0x00,
+            1, (-2i8) as u8, 0, 0xFF, 0, 0xFF,
+            0xFF,
+        ];
+
+        let image = decode(6, 1, 8, &data);
+
+        let pixels: Vec<_> = (0..6).map(|x| image.pixel(x, 0)).collect();
+        assert_eq!(pixels[0], [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(pixels[1], [0, 0, 0, 0xFF]);
+        assert_eq!(pixels[4], pixels[0]);
+        assert_eq!(pixels[5], pixels[1]);
+    }
+
+    #[test]
+    fn unchanged_frame() {
+        let image = decode(2, 2, 16, &[0, 0, 0, 4]);
+
+        assert_eq!(*image.format(), PixelFormat::RGB555);
+        assert!(image.data().iter().all(|&b| b == 0));
+    }
+}
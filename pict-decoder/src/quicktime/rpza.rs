@@ -0,0 +1,164 @@
+use tracing::warn;
+
+use crate::{
+    image::{Image, PixelFormat},
+    Error, Result,
+};
+
+use super::{blocks::Blocks, read_u16, read_u32, read_u8, ImageDescription};
+
+const CODEC: [u8; 4] = *b"rpza";
+
+/// Two colors and the two interpolated between them, as indexed by the block's bits
+fn interpolate(color_a: u16, color_b: u16) -> [u16; 4] {
+    let mut ret = [color_b, 0, 0, color_a];
+    for shift in [10, 5, 0] {
+        let a = (color_a >> shift) & 0x1F;
+        let b = (color_b >> shift) & 0x1F;
+        ret[1] |= ((11 * a + 21 * b) >> 5) << shift;
+        ret[2] |= ((21 * a + 11 * b) >> 5) << shift;
+    }
+
+    ret
+}
+
+/// Apple Video codec, 4x4 blocks of 15 bits colors
+pub(super) fn decode(description: &ImageDescription, data: &[u8]) -> Result<Image> {
+    let mut blocks = Blocks::new(description.width, description.height);
+    let stride = blocks.width();
+    let mut pixels = vec![0u16; stride * blocks.height()];
+
+    let mut buf = data;
+    let header = read_u32(&mut buf)?;
+    if header >> 24 != 0xE1 {
+        warn!("unexpected rpza header: {:08x}", header)
+    }
+
+    while !buf.is_empty() && !blocks.is_done() {
+        let mut opcode = read_u8(&mut buf)?;
+        let mut count = (opcode & 0x1F) as usize + 1;
+
+        // first color of a block of 16 colors, or of 4 colors if directly followed by another
+        let mut color_a = 0;
+        if opcode & 0x80 == 0 {
+            color_a = u16::from_be_bytes([opcode, read_u8(&mut buf)?]);
+            opcode = 0x00;
+            if buf.first().is_some_and(|next| next & 0x80 != 0) {
+                opcode = 0x20;
+                count = 1;
+            }
+        }
+
+        match opcode & 0xE0 {
+            0x80 => (0..count).for_each(|_| {
+                blocks.next();
+            }),
+            0xA0 => {
+                let color = read_u16(&mut buf)?;
+                for (x, y) in (0..count).map_while(|_| blocks.next()) {
+                    for row in 0..4 {
+                        let start = (y + row) * stride + x;
+                        pixels[start..start + 4].fill(color);
+                    }
+                }
+            }
+            0xC0 | 0x20 => {
+                if opcode & 0xE0 == 0xC0 {
+                    color_a = read_u16(&mut buf)?;
+                }
+                let colors = interpolate(color_a, read_u16(&mut buf)?);
+                for (x, y) in (0..count).map_while(|_| blocks.next()) {
+                    for row in 0..4 {
+                        let indexes = read_u8(&mut buf)?;
+                        let start = (y + row) * stride + x;
+                        for (column, pixel) in pixels[start..start + 4].iter_mut().enumerate() {
+                            *pixel = colors[(indexes >> (2 * (3 - column))) as usize & 0x03];
+                        }
+                    }
+                }
+            }
+            0x00 => {
+                if let Some((x, y)) = blocks.next() {
+                    for row in 0..4 {
+                        let start = (y + row) * stride + x;
+                        for (column, pixel) in pixels[start..start + 4].iter_mut().enumerate() {
+                            *pixel = match (row, column) {
+                                (0, 0) => color_a,
+                                _ => read_u16(&mut buf)?,
+                            };
+                        }
+                    }
+                }
+            }
+            _ => return Err(Error::CorruptedCodecData(CODEC)),
+        }
+    }
+
+    Image::new(
+        blocks.width(),
+        blocks.height(),
+        PixelFormat::RGB555,
+        pixels.into_iter().flat_map(u16::to_be_bytes).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::PictBuilder, PICT};
+
+    use super::*;
+
+    fn decode(width: u16, height: u16, data: &[u8]) -> Image {
+        let mut chunk = (0xE1000000 | (data.len() as u32 + 4))
+            .to_be_bytes()
+            .to_vec();
+        chunk.extend_from_slice(data);
+        let raw = PictBuilder::new(width, height)
+            .compressed_quicktime(b"rpza", (width, height, 16), (0, 0, height, width), &chunk)
+            .build();
+
+        PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode")
+    }
+
+    #[test]
+    fn interpolated_colors() {
+        assert_eq!(
+            interpolate(0x7C00, 0x0000),
+            [0x0000, 0x2800, 0x5000, 0x7C00]
+        );
+    }
+
+    #[test]
+    fn fill_and_skip() {
+        // two red blocks, skip one, then a four colors block
+        #[rustfmt::skip]
+        let data = [
+            0xA1, 0x7C, 0x00,
+            0x80,
+            0xC0, 0x03, 0xE0, 0x00, 0x1F, 0x00, 0xFF, 0x00, 0xFF,
+        ];
+
+        let image = decode(8, 8, &data);
+
+        assert_eq!((image.width(), image.height()), (8, 8));
+        assert_eq!(image.pixel(7, 3), [0xFF, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(0, 4), [0, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(4, 4), [0, 0, 0xFF, 0xFF]);
+        assert_eq!(image.pixel(7, 5), [0, 0xFF, 0, 0xFF]);
+    }
+
+    #[test]
+    fn sixteen_colors() {
+        let mut data = vec![0x7C, 0x00];
+        for _ in 1..16 {
+            data.extend_from_slice(&0x001Fu16.to_be_bytes());
+        }
+
+        let image = decode(4, 4, &data);
+
+        assert_eq!(image.pixel(0, 0), [0xFF, 0, 0, 0xFF]);
+        assert_eq!(image.pixel(3, 3), [0, 0, 0xFF, 0xFF]);
+    }
+}
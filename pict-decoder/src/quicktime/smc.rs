@@ -0,0 +1,235 @@
+use crate::{
+    image::{Image, PixelFormat},
+    Error, Result,
+};
+
+use super::{blocks::Blocks, palette, read_u16, read_u32, read_u8, ImageDescription};
+
+const CODEC: [u8; 4] = *b"smc ";
+
+/// Recently used groups of colors, referenced by later blocks
+struct ColorCache<const N: usize> {
+    entries: Vec<[u8; N]>,
+    next: u8,
+}
+
+impl<const N: usize> ColorCache<N> {
+    fn new() -> Self {
+        Self {
+            entries: vec![[0; N]; 256],
+            next: 0,
+        }
+    }
+
+    /// Colors either read and cached, or referenced by index, as told by the opcode
+    fn get(&mut self, buf: &mut &[u8], is_new: bool) -> Result<[u8; N]> {
+        if !is_new {
+            return Ok(self.entries[read_u8(buf)? as usize]);
+        }
+
+        let colors: [u8; N] = buf
+            .get(..N)
+            .ok_or(Error::UnexpectedEOB)?
+            .try_into()
+            .expect("slice of cached size");
+        *buf = &buf[N..];
+
+        self.entries[self.next as usize] = colors;
+        self.next = self.next.wrapping_add(1);
+
+        Ok(colors)
+    }
+}
+
+/// Paint the next block, with the color of each of its 16 pixels
+fn paint(
+    blocks: &mut Blocks,
+    pixels: &mut [u8],
+    mut color_of: impl FnMut(usize) -> Result<u8>,
+) -> Result<()> {
+    let stride = blocks.width();
+    let Some((x, y)) = blocks.next() else {
+        return Ok(());
+    };
+
+    for i in 0..16 {
+        pixels[(y + i / 4) * stride + x + i % 4] = color_of(i)?;
+    }
+
+    Ok(())
+}
+
+/// Graphics codec, 4x4 blocks of indexed colors
+pub(super) fn decode(description: &ImageDescription, data: &[u8]) -> Result<Image> {
+    if description.depth != 8 {
+        return Err(Error::UnsupportedCodecDepth(CODEC, description.depth));
+    }
+
+    let mut blocks = Blocks::new(description.width, description.height);
+    let stride = blocks.width();
+    let mut pixels = vec![0u8; stride * blocks.height()];
+
+    let mut pairs = ColorCache::<2>::new();
+    let mut quads = ColorCache::<4>::new();
+    let mut octets = ColorCache::<8>::new();
+
+    let mut buf = data;
+    read_u32(&mut buf)?; // flags and chunk size
+
+    while !blocks.is_done() {
+        let opcode = read_u8(&mut buf)?;
+        // either a longer count or a cached colors index
+        let is_extended = opcode & 0x10 != 0;
+        let count = match opcode & 0xE0 {
+            0x00..=0x60 if is_extended => read_u8(&mut buf)? as usize + 1,
+            _ => (opcode & 0x0F) as usize + 1,
+        };
+
+        match opcode & 0xE0 {
+            // skip blocks
+            0x00 => (0..count).for_each(|_| {
+                blocks.next();
+            }),
+            // repeat the last block, or alternate the last two
+            0x20 | 0x40 => {
+                let (history, count) = match opcode & 0xE0 {
+                    0x20 => (1, count),
+                    _ => (2, count * 2),
+                };
+                let start = blocks
+                    .index()
+                    .checked_sub(history)
+                    .ok_or(Error::CorruptedCodecData(CODEC))?;
+                for i in 0..count {
+                    let (from_x, from_y) = blocks.corner(start + i % history);
+                    let Some((to_x, to_y)) = blocks.next() else {
+                        break;
+                    };
+                    for row in 0..4 {
+                        let from = (from_y + row) * stride + from_x;
+                        pixels.copy_within(from..from + 4, (to_y + row) * stride + to_x);
+                    }
+                }
+            }
+            // single color
+            0x60 => {
+                let color = read_u8(&mut buf)?;
+                for _ in 0..count {
+                    paint(&mut blocks, &mut pixels, |_| Ok(color))?;
+                }
+            }
+            // two colors, one bit per pixel
+            0x80 => {
+                let colors = pairs.get(&mut buf, !is_extended)?;
+                for _ in 0..count {
+                    let flags = read_u16(&mut buf)?;
+                    paint(&mut blocks, &mut pixels, |i| {
+                        Ok(colors[(flags >> (15 - i)) as usize & 0x01])
+                    })?;
+                }
+            }
+            // four colors, two bits per pixel
+            0xA0 => {
+                let colors = quads.get(&mut buf, !is_extended)?;
+                for _ in 0..count {
+                    let flags = read_u32(&mut buf)?;
+                    paint(&mut blocks, &mut pixels, |i| {
+                        Ok(colors[(flags >> (30 - 2 * i)) as usize & 0x03])
+                    })?;
+                }
+            }
+            // eight colors, three bits per pixel, split over the low nibbles of the words
+            0xC0 => {
+                let colors = octets.get(&mut buf, !is_extended)?;
+                for _ in 0..count {
+                    let a = read_u16(&mut buf)? as u32;
+                    let b = read_u16(&mut buf)? as u32;
+                    let c = read_u16(&mut buf)? as u32;
+                    let top = ((a & 0xFFF0) << 8) | (b >> 4);
+                    let bottom =
+                        ((c & 0xFFF0) << 8) | ((a & 0x0F) << 8) | ((b & 0x0F) << 4) | (c & 0x0F);
+                    paint(&mut blocks, &mut pixels, |i| {
+                        let flags = if i < 8 { top } else { bottom };
+                        Ok(colors[(flags >> (21 - 3 * (i % 8))) as usize & 0x07])
+                    })?;
+                }
+            }
+            // sixteen colors
+            _ => {
+                for _ in 0..count {
+                    paint(&mut blocks, &mut pixels, |_| read_u8(&mut buf))?;
+                }
+            }
+        }
+    }
+
+    let palette = palette::default(description.depth).expect("palette of 8 bits");
+    Image::new(
+        stride,
+        blocks.height(),
+        PixelFormat::Indexed8 { palette },
+        pixels,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::PictBuilder, PICT};
+
+    use super::*;
+
+    fn decode(width: u16, height: u16, data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32 + 4).to_be_bytes().to_vec();
+        chunk.extend_from_slice(data);
+        let raw = PictBuilder::new(width, height)
+            .compressed_quicktime(b"smc ", (width, height, 8), (0, 0, height, width), &chunk)
+            .build();
+
+        let image = PICT::parse(raw.as_slice())
+            .and_then(|pict| pict.to_image())
+            .expect("to decode");
+        assert!(matches!(image.format(), PixelFormat::Indexed8 { .. }));
+
+        image.into_data()
+    }
+
+    #[test]
+    fn fill_and_repeat() {
+        // one block of color 5, repeated twice, then the last two alternated once
+        let pixels = decode(20, 4, &[0x60, 5, 0x21, 0x40]);
+
+        assert!(pixels.iter().all(|&p| p == 5));
+    }
+
+    #[test]
+    fn cached_pairs() {
+        #[rustfmt::skip]
+        let data = [
+            0x80, 1, 2, 0xFF, 0x00, // new pair, top half of the second color
+            0x90, 0, 0x00, 0xFF, // cached pair, bottom half
+        ];
+
+        let pixels = decode(8, 4, &data);
+
+        assert_eq!(pixels[..8], [2, 2, 2, 2, 1, 1, 1, 1]);
+        assert_eq!(pixels[3 * 8..], [1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn octets_interleaved() {
+        let mut data = vec![0xC0, 0, 1, 2, 3, 4, 5, 6, 7];
+        // pixel i of each half takes color i, low nibbles hold the bottom's first pixels
+        let top: u32 = (0..8).fold(0, |acc, i| (acc << 3) | i);
+        let bottom = top;
+        let a = ((top >> 8) & 0xFFF0) | ((bottom >> 8) & 0x0F);
+        let b = ((top << 4) & 0xFFF0) | ((bottom >> 4) & 0x0F);
+        let c = ((bottom >> 8) & 0xFFF0) | (bottom & 0x0F);
+        for word in [a, b, c] {
+            data.extend_from_slice(&(word as u16).to_be_bytes());
+        }
+
+        let pixels = decode(4, 4, &data);
+
+        assert_eq!(pixels, [0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+}
@@ -1,14 +1,16 @@
+use std::fmt;
+
 use bytes::Buf;
 
 use crate::{utils::ensure_remains_bytes, Result};
 
-#[derive(Debug)]
-#[allow(dead_code)]
-pub(crate) struct Rectangle {
-    pub(crate) top: u16,
-    pub(crate) left: u16,
-    pub(crate) bottom: u16,
-    pub(crate) right: u16,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// QuickDraw rectangle, of edges between pixels
+pub struct Rectangle {
+    pub top: i16,
+    pub left: i16,
+    pub bottom: i16,
+    pub right: i16,
 }
 
 impl Rectangle {
@@ -16,10 +18,39 @@ impl Rectangle {
         let mut buf = ensure_remains_bytes(buf, 8)?;
 
         Ok(Self {
-            top: buf.get_u16(),
-            left: buf.get_u16(),
-            bottom: buf.get_u16(),
-            right: buf.get_u16(),
+            top: buf.get_i16(),
+            left: buf.get_i16(),
+            bottom: buf.get_i16(),
+            right: buf.get_i16(),
         })
     }
+
+    /// Horizontal size, empty if reversed
+    pub fn width(&self) -> usize {
+        (self.right as i32 - self.left as i32).max(0) as usize
+    }
+
+    /// Left, top, right and bottom edges, to compute without overflows
+    pub(crate) fn area(&self) -> (i32, i32, i32, i32) {
+        (
+            self.left as i32,
+            self.top as i32,
+            self.right as i32,
+            self.bottom as i32,
+        )
+    }
+
+    /// Vertical size, empty if reversed
+    pub fn height(&self) -> usize {
+        (self.bottom as i32 - self.top as i32).max(0) as usize
+    }
+}
+
+impl fmt::Display for Rectangle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "({}, {})-({}, {})",
+            self.top, self.left, self.bottom, self.right
+        ))
+    }
 }
@@ -0,0 +1,349 @@
+use std::{cmp, fmt};
+
+use bytes::Buf;
+
+use crate::{rectangle::Rectangle, utils::ensure_remains_bytes, Error, Result};
+
+/// End of a scanline, and of the scanlines
+const END: i16 = 0x7FFF;
+
+/// QuickDraw region, as a bounding box and the inversion points of its scanlines
+///
+/// Each inversion point flips the pixels at its right and below it.
+#[derive(Clone)]
+pub struct Region {
+    pub(crate) bounds: Rectangle,
+    /// Horizontal inversions by line, in increasing order
+    scanlines: Vec<(i16, Vec<i16>)>,
+}
+
+impl Region {
+    const HEADER_SIZE: usize = 2 + 8;
+
+    pub(crate) fn parse(mut buf: impl Buf) -> Result<Self> {
+        let (size, bounds) = {
+            let mut header = ensure_remains_bytes(&mut buf, Self::HEADER_SIZE)?;
+            (header.get_u16() as usize, Rectangle::parse(&mut header)?)
+        };
+        let data_size = size
+            .checked_sub(Self::HEADER_SIZE)
+            .ok_or(Error::InvalidRegionSize(size as u16))?;
+        let mut buf = ensure_remains_bytes(buf, data_size)?;
+
+        let mut next = || -> Result<i16> {
+            if buf.remaining() < 2 {
+                return Err(Error::InvalidRegionSize(size as u16));
+            }
+            Ok(buf.get_i16())
+        };
+
+        let mut scanlines = Vec::new();
+        if data_size > 0 {
+            loop {
+                let y = next()?;
+                if y == END {
+                    break;
+                }
+
+                let mut inversions = Vec::new();
+                loop {
+                    match next()? {
+                        END => break,
+                        x => inversions.push(x),
+                    }
+                }
+                scanlines.push((y, inversions));
+            }
+        }
+
+        Ok(Self { bounds, scanlines })
+    }
+
+    /// Whether it is only its bounding box
+    pub(crate) fn is_rectangular(&self) -> bool {
+        self.scanlines.is_empty()
+    }
+
+    /// Pixels inside the region, only computed for the given area
+    pub(crate) fn mask(&self, within: (i32, i32, i32, i32)) -> Mask {
+        let mut ret = Mask::from_fn(intersect(self.bounds.area(), within), |_, _| true);
+        if self.is_rectangular() {
+            return ret;
+        }
+
+        ret.bits.fill(false);
+        let mut row = vec![false; ret.width];
+        let mut scanlines = self.scanlines.iter().peekable();
+        for y in 0..ret.height {
+            let line = ret.top + y as i32;
+            while let Some((_, inversions)) = scanlines.next_if(|(y, _)| (*y as i32) <= line) {
+                for &x in inversions {
+                    let start = (x as i32 - ret.left).clamp(0, ret.width as i32) as usize;
+                    row[start..].iter_mut().for_each(|pixel| *pixel = !*pixel);
+                }
+            }
+            ret.bits[y * ret.width..(y + 1) * ret.width].copy_from_slice(&row);
+        }
+
+        ret
+    }
+
+    /// Bounding box of the region
+    pub fn bounds(&self) -> Rectangle {
+        self.bounds
+    }
+
+    /// Human readable description of the fields, for debugging
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("bounds", self.bounds.to_string()),
+            ("scanlines", self.scanlines.len().to_string()),
+        ]
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("{}", self.bounds))
+    }
+}
+
+/// Common part of areas given as left, top, right and bottom
+pub(crate) fn intersect(
+    (left, top, right, bottom): (i32, i32, i32, i32),
+    (other_left, other_top, other_right, other_bottom): (i32, i32, i32, i32),
+) -> (i32, i32, i32, i32) {
+    (
+        cmp::max(left, other_left),
+        cmp::max(top, other_top),
+        cmp::min(right, other_right),
+        cmp::min(bottom, other_bottom),
+    )
+}
+
+/// Rasterized region, covering its bounding box
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Mask {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    bits: Vec<bool>,
+}
+
+impl Mask {
+    /// Pixels of the area for which the predicate holds
+    pub(crate) fn from_fn(
+        (left, top, right, bottom): (i32, i32, i32, i32),
+        is_inside: impl Fn(i32, i32) -> bool,
+    ) -> Self {
+        let width = cmp::max(right - left, 0) as usize;
+        let height = cmp::max(bottom - top, 0) as usize;
+
+        let bits = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| (left + x, top + y)))
+            .map(|(x, y)| is_inside(x, y))
+            .collect();
+
+        Self {
+            left,
+            top,
+            width,
+            height,
+            bits,
+        }
+    }
+
+    pub(crate) fn contains(&self, x: i32, y: i32) -> bool {
+        let (x, y) = (x - self.left, y - self.top);
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return false;
+        }
+
+        self.bits[y as usize * self.width + x as usize]
+    }
+
+    /// Combine both masks over the given area
+    fn combine(
+        &self,
+        other: &Self,
+        area: (i32, i32, i32, i32),
+        op: impl Fn(bool, bool) -> bool,
+    ) -> Self {
+        Self::from_fn(area, |x, y| op(self.contains(x, y), other.contains(x, y)))
+    }
+
+    /// Area covered, as left, top, right and bottom
+    pub(crate) fn area(&self) -> (i32, i32, i32, i32) {
+        (
+            self.left,
+            self.top,
+            self.left + self.width as i32,
+            self.top + self.height as i32,
+        )
+    }
+
+    /// Pixels inside, from the top left
+    pub(crate) fn points(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.bits
+            .iter()
+            .enumerate()
+            .filter(|(_, &bit)| bit)
+            .map(|(i, _)| {
+                (
+                    self.left + (i % self.width) as i32,
+                    self.top + (i / self.width) as i32,
+                )
+            })
+    }
+
+    /// Same pixels, moved by the given distances
+    pub(crate) fn offset(mut self, dx: i32, dy: i32) -> Self {
+        self.left += dx;
+        self.top += dy;
+        self
+    }
+
+    /// Pixels staying inside when moved by up to the given distances in every direction
+    pub(crate) fn eroded(&self, dx: i32, dy: i32) -> Self {
+        let horizontal = Self::from_fn(self.area(), |x, y| {
+            (-dx..=dx).all(|offset| self.contains(x + offset, y))
+        });
+
+        Self::from_fn(self.area(), |x, y| {
+            (-dy..=dy).all(|offset| horizontal.contains(x, y + offset))
+        })
+    }
+
+    /// Pixels inside but not in the other
+    pub(crate) fn difference(&self, other: &Self) -> Self {
+        self.combine(other, self.area(), |a, b| a && !b)
+    }
+
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        self.combine(
+            other,
+            (
+                cmp::min(self.left, other.left),
+                cmp::min(self.top, other.top),
+                cmp::max(
+                    self.left + self.width as i32,
+                    other.left + other.width as i32,
+                ),
+                cmp::max(
+                    self.top + self.height as i32,
+                    other.top + other.height as i32,
+                ),
+            ),
+            |a, b| a || b,
+        )
+    }
+
+    pub(crate) fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, intersect(self.area(), other.area()), |a, b| a && b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHOLE: (i32, i32, i32, i32) = (
+        i16::MIN as i32,
+        i16::MIN as i32,
+        i16::MAX as i32,
+        i16::MAX as i32,
+    );
+
+    fn region(bounds: (i16, i16, i16, i16), scanlines: &[i16]) -> Vec<u8> {
+        let mut ret = ((10 + scanlines.len() * 2) as u16).to_be_bytes().to_vec();
+        for value in [bounds.0, bounds.1, bounds.2, bounds.3]
+            .into_iter()
+            .chain(scanlines.iter().copied())
+        {
+            ret.extend_from_slice(&value.to_be_bytes());
+        }
+        ret
+    }
+
+    fn rows(mask: &Mask) -> Vec<String> {
+        (0..mask.height as i32)
+            .map(|y| {
+                (0..mask.width as i32)
+                    .map(|x| match mask.contains(mask.left + x, mask.top + y) {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rectangular() {
+        let region = Region::parse(region((1, 2, 3, 5), &[]).as_slice()).unwrap();
+
+        assert!(region.is_rectangular());
+        assert_eq!(rows(&region.mask(WHOLE)), ["###", "###"]);
+        assert!(region.mask(WHOLE).contains(2, 1));
+        assert!(!region.mask(WHOLE).contains(1, 1));
+    }
+
+    #[test]
+    fn inversion_points() {
+        // an L shape: 4x2 on top of 2x2
+        #[rustfmt::skip]
+        let raw = region((0, 0, 4, 4), &[
+            0, 0, 4, END,
+            2, 2, 4, END,
+            4, 0, 2, END,
+            END,
+        ]);
+
+        let region = Region::parse(raw.as_slice()).unwrap();
+
+        assert_eq!(rows(&region.mask(WHOLE)), ["####", "####", "##..", "##.."]);
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let a = Mask::from_fn((0, 0, 2, 2), |_, _| true);
+        let b = Mask::from_fn((1, 1, 3, 3), |_, _| true);
+
+        assert_eq!(rows(&a.union(&b)), ["##.", "###", ".##"]);
+        assert_eq!(rows(&a.intersection(&b)), ["#"]);
+        assert_eq!(a.intersection(&b).left, 1);
+
+        let c = Mask::from_fn((5, 5, 6, 6), |_, _| true);
+        assert!(rows(&a.intersection(&c)).is_empty());
+    }
+
+    #[test]
+    fn only_within_area() {
+        let region = Region::parse(region((-1000, -1000, 1000, 1000), &[]).as_slice()).unwrap();
+
+        let mask = region.mask((0, 0, 2, 1));
+
+        assert_eq!(rows(&mask), ["##"]);
+        assert_eq!(mask.area(), (0, 0, 2, 1));
+    }
+
+    #[test]
+    fn eroded_border() {
+        let mask = Mask::from_fn((0, 0, 4, 3), |_, _| true);
+
+        let border = mask.difference(&mask.eroded(1, 1));
+
+        assert_eq!(rows(&border), ["####", "#..#", "####"]);
+    }
+
+    #[test]
+    fn truncated() {
+        let raw = region((0, 0, 1, 1), &[0, 0, 1]);
+
+        assert!(matches!(
+            Region::parse(raw.as_slice()),
+            Err(Error::InvalidRegionSize(16))
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+use bytes::Buf;
+use strum::FromRepr;
+
+use crate::{
+    point::Point, rectangle::Rectangle, region::Region, utils::ensure_remains_bytes, Error, Result,
+};
+
+/// How a shape is drawn, the low bits of its opcode
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromRepr, strum::Display)]
+#[repr(u16)]
+pub enum Verb {
+    /// Outline with the pen
+    Frame = 0,
+    /// Inside with the pen
+    Paint = 1,
+    /// Inside with the background pattern
+    Erase = 2,
+    /// Inside with inverted colors
+    Invert = 3,
+    /// Inside with the fill pattern
+    Fill = 4,
+}
+
+/// Closed outline, drawn between consecutive points
+#[derive(Clone)]
+pub struct Polygon {
+    pub bounds: Rectangle,
+    pub points: Vec<Point>,
+}
+
+impl Polygon {
+    const HEADER_SIZE: usize = 2 + 8;
+
+    pub(crate) fn parse(mut buf: impl Buf) -> Result<Self> {
+        let (size, bounds) = {
+            let mut header = ensure_remains_bytes(&mut buf, Self::HEADER_SIZE)?;
+            (header.get_u16() as usize, Rectangle::parse(&mut header)?)
+        };
+        let points_size = size
+            .checked_sub(Self::HEADER_SIZE)
+            .filter(|size| size % 4 == 0)
+            .ok_or(Error::InvalidPolygonSize(size as u16))?;
+
+        let mut buf = ensure_remains_bytes(buf, points_size)?;
+        let points = (0..points_size / 4)
+            .map(|_| Point::parse(&mut buf))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { bounds, points })
+    }
+}
+
+/// Geometry of a drawing opcode, `None` to reuse the previous one of its kind
+pub enum Shape {
+    Rect(Option<Rectangle>),
+    /// Rectangle with corners rounded by the oval size
+    RRect(Option<Rectangle>),
+    Oval(Option<Rectangle>),
+    /// Part of the oval in the rectangle, in degrees clockwise from the top
+    Arc {
+        rect: Option<Rectangle>,
+        start: i16,
+        angle: i16,
+    },
+    Poly(Option<Polygon>),
+    Rgn(Option<Region>),
+}
+
+impl Shape {
+    /// Opcode drawing the first of the verbs of the shape
+    const fn base_opcode(&self) -> u16 {
+        let (base, is_same) = match self {
+            Self::Rect(rect) => (0x0030, rect.is_none()),
+            Self::RRect(rect) => (0x0040, rect.is_none()),
+            Self::Oval(rect) => (0x0050, rect.is_none()),
+            Self::Arc { rect, .. } => (0x0060, rect.is_none()),
+            Self::Poly(poly) => (0x0070, poly.is_none()),
+            Self::Rgn(region) => (0x0080, region.is_none()),
+        };
+
+        match is_same {
+            true => base + 0x08,
+            false => base,
+        }
+    }
+
+    /// Raw opcode drawing the shape with the verb
+    pub(crate) const fn opcode(&self, verb: Verb) -> u16 {
+        self.base_opcode() + verb as u16
+    }
+
+    /// Split a shape opcode in its verb and shape
+    pub(crate) fn parse(raw: u16, mut buf: impl Buf) -> Result<(Verb, Self)> {
+        let verb = Verb::from_repr(raw & 0x07)
+            .filter(|_| (0x0030..0x0090).contains(&raw))
+            .ok_or(Error::UnsupportedOpcode(raw))?;
+        let is_same = raw & 0x08 != 0;
+
+        let mut rect = || -> Result<_> {
+            match is_same {
+                true => Ok(None),
+                false => Rectangle::parse(&mut buf).map(Some),
+            }
+        };
+
+        let shape = match raw & 0xF0 {
+            0x30 => Self::Rect(rect()?),
+            0x40 => Self::RRect(rect()?),
+            0x50 => Self::Oval(rect()?),
+            0x60 => {
+                let rect = rect()?;
+                let mut angles = ensure_remains_bytes(&mut buf, 4)?;
+                Self::Arc {
+                    rect,
+                    start: angles.get_i16(),
+                    angle: angles.get_i16(),
+                }
+            }
+            0x70 => Self::Poly(match is_same {
+                true => None,
+                false => Some(Polygon::parse(&mut buf)?),
+            }),
+            _ => Self::Rgn(match is_same {
+                true => None,
+                false => Some(Region::parse(&mut buf)?),
+            }),
+        };
+
+        Ok((verb, shape))
+    }
+
+    /// Human readable description of the fields, for debugging
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::Rect(rect) | Self::RRect(rect) | Self::Oval(rect) => {
+                rect.iter().map(|rect| ("rect", rect.to_string())).collect()
+            }
+            Self::Arc { rect, start, angle } => {
+                let mut ret: Vec<_> = rect.iter().map(|rect| ("rect", rect.to_string())).collect();
+                ret.extend([
+                    ("start angle", start.to_string()),
+                    ("arc angle", angle.to_string()),
+                ]);
+                ret
+            }
+            Self::Poly(poly) => poly
+                .iter()
+                .flat_map(|poly| {
+                    [
+                        ("bounds", poly.bounds.to_string()),
+                        ("points", poly.points.len().to_string()),
+                    ]
+                })
+                .collect(),
+            Self::Rgn(region) => region.iter().flat_map(Region::fields).collect(),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+/// Builder of version 2 pictures, to test parsing of crafted opcodes
+pub struct PictBuilder(Vec<u8>);
+
+pub fn rect(top: u16, left: u16, bottom: u16, right: u16) -> Vec<u8> {
+    [top, left, bottom, right]
+        .into_iter()
+        .flat_map(u16::to_be_bytes)
+        .collect()
+}
+
+/// Version 1 picture of byte opcodes, ended by OpEndPic
+pub fn version_1(width: u16, height: u16, ops: &[u8]) -> Vec<u8> {
+    let mut ret = vec![0; 512];
+    ret.extend_from_slice(&0u16.to_be_bytes());
+    ret.extend(rect(0, 0, height, width));
+    ret.extend_from_slice(&[0x11, 0x01]);
+    ret.extend_from_slice(ops);
+    ret.push(0xFF);
+    ret
+}
+
+/// NFNT of 'A' as a 2x2 square advancing by 3 and a missing glyph 1 pixel wide, 3 pixels high
+pub fn nfnt() -> Vec<u8> {
+    let mut ret = Vec::new();
+    for value in [
+        0x9000u16, // proportional
+        b'A' as u16,
+        b'A' as u16,
+        3,      // max width
+        0,      // kern max
+        0xFFFF, // negated descent
+        3,      // rect width
+        3,      // rect height
+        0,      // widths offset, set below
+        2,      // ascent
+        1,      // descent
+        0,      // leading
+        1,      // row words
+    ] {
+        ret.extend_from_slice(&value.to_be_bytes());
+    }
+    ret.extend_from_slice(&[0b1110_0000, 0, 0b1110_0000, 0, 0, 0]);
+    for location in [0u16, 2, 3, 3] {
+        ret.extend_from_slice(&location.to_be_bytes());
+    }
+    let widths_offset = ((ret.len() - 16) / 2) as u16;
+    ret[16..18].copy_from_slice(&widths_offset.to_be_bytes());
+    for offset_and_width in [0x0003u16, 0x0002, 0xFFFF] {
+        ret.extend_from_slice(&offset_and_width.to_be_bytes());
+    }
+
+    ret
+}
+
+impl PictBuilder {
+    /// Picture with an extended version 2 header
+    pub fn new(width: u16, height: u16) -> Self {
+        let mut ret = vec![0; 512];
+        ret.extend_from_slice(&0u16.to_be_bytes());
+        ret.extend(rect(0, 0, height, width));
+
+        let mut ret = Self(ret)
+            .op(0x0011, &[0x02, 0xFF])
+            .op(0x0C00, &(-2i16).to_be_bytes());
+        ret.0.extend_from_slice(&[0; 2]);
+        ret.0.extend_from_slice(&(72u32 << 16).to_be_bytes());
+        ret.0.extend_from_slice(&(72u32 << 16).to_be_bytes());
+        ret.0.extend(rect(0, 0, height, width));
+        ret.0.extend_from_slice(&[0; 4]);
+
+        ret
+    }
+
+    /// Picture with an original version 2 header, of fixed point bounds
+    pub fn original(width: u16, height: u16) -> Self {
+        let mut ret = vec![0; 512];
+        ret.extend_from_slice(&0u16.to_be_bytes());
+        ret.extend(rect(0, 0, height, width));
+
+        let mut ret = Self(ret)
+            .op(0x0011, &[0x02, 0xFF])
+            .op(0x0C00, &(-1i32).to_be_bytes());
+        for edge in [0, 0, width, height] {
+            ret.0
+                .extend_from_slice(&((edge as u32) << 16).to_be_bytes());
+        }
+        ret.0.extend_from_slice(&[0; 4]);
+
+        ret
+    }
+
+    /// Append an opcode and its raw content, padding it to an even size
+    pub fn op(mut self, opcode: u16, content: &[u8]) -> Self {
+        self.0.extend_from_slice(&opcode.to_be_bytes());
+        self.0.extend_from_slice(content);
+        if self.0.len() % 2 == 1 {
+            self.0.push(0);
+        }
+
+        self
+    }
+
+    /// Append a 32 bits DirectBitsRect without packing
+    pub fn direct_bits_rect(self, width: u16, height: u16, pixels: &[u8]) -> Self {
+        self.direct_bits((width, height, 32), (1, width * 4), 3, pixels)
+    }
+
+    /// Append a DirectBitsRect with already encoded rows, packing is pack type and row bytes
+    pub fn direct_bits(
+        self,
+        (width, height, pixel_size): (u16, u16, u16),
+        (pack_type, row_bytes): (u16, u16),
+        components_count: u16,
+        data: &[u8],
+    ) -> Self {
+        self.direct_bits_to(
+            (rect(0, 0, height, width), 0),
+            (width, height, pixel_size),
+            (pack_type, row_bytes),
+            components_count,
+            data,
+        )
+    }
+
+    /// Append a DirectBitsRect drawn at the given destination rectangle with the transfer mode
+    pub fn direct_bits_to(
+        self,
+        (destination, mode): (Vec<u8>, u16),
+        (width, height, pixel_size): (u16, u16, u16),
+        (pack_type, row_bytes): (u16, u16),
+        components_count: u16,
+        data: &[u8],
+    ) -> Self {
+        let mut content = Vec::new();
+        content.extend_from_slice(&0xFFu32.to_be_bytes());
+        content.extend_from_slice(&(0x8000 | row_bytes).to_be_bytes());
+        content.extend(rect(0, 0, height, width));
+        content.extend_from_slice(&0u16.to_be_bytes()); // version
+        content.extend_from_slice(&pack_type.to_be_bytes());
+        content.extend_from_slice(&0u32.to_be_bytes()); // pack size
+        content.extend_from_slice(&(72u32 << 16).to_be_bytes());
+        content.extend_from_slice(&(72u32 << 16).to_be_bytes());
+        content.extend_from_slice(&16u16.to_be_bytes()); // pixel type
+        content.extend_from_slice(&pixel_size.to_be_bytes());
+        content.extend_from_slice(&components_count.to_be_bytes());
+        content.extend_from_slice(&(if pixel_size == 16 { 5u16 } else { 8 }).to_be_bytes());
+        content.extend_from_slice(&[0; 4 + 4 + 4]); // plane, color table, reserved
+        content.extend(rect(0, 0, height, width));
+        content.extend(destination);
+        content.extend_from_slice(&mode.to_be_bytes());
+        content.extend_from_slice(data);
+
+        self.op(0x009A, &content)
+    }
+
+    /// Append an indexed BitsRect, or a PackBitsRect with literal runs, a BitMap without palette
+    pub fn bits_rect(
+        self,
+        packed: bool,
+        (width, height, pixel_size): (u16, u16, u16),
+        palette: &[[u8; 3]],
+        rows: &[u8],
+    ) -> Self {
+        let row_bytes = (rows.len() / height as usize) as u16;
+
+        let mut content = Vec::new();
+        match palette.is_empty() {
+            true => content.extend_from_slice(&row_bytes.to_be_bytes()),
+            false => content.extend_from_slice(&(0x8000 | row_bytes).to_be_bytes()),
+        }
+        content.extend(rect(0, 0, height, width));
+        if !palette.is_empty() {
+            content.extend_from_slice(&0u16.to_be_bytes()); // version
+            content.extend_from_slice(&0u16.to_be_bytes()); // pack type
+            content.extend_from_slice(&0u32.to_be_bytes()); // pack size
+            content.extend_from_slice(&(72u32 << 16).to_be_bytes());
+            content.extend_from_slice(&(72u32 << 16).to_be_bytes());
+            content.extend_from_slice(&0u16.to_be_bytes()); // pixel type
+            content.extend_from_slice(&pixel_size.to_be_bytes());
+            content.extend_from_slice(&1u16.to_be_bytes()); // components count
+            content.extend_from_slice(&pixel_size.to_be_bytes()); // components size
+            content.extend_from_slice(&[0; 4 + 4 + 4]); // plane, color table, reserved
+
+            content.extend_from_slice(&0u32.to_be_bytes()); // seed
+            content.extend_from_slice(&0u16.to_be_bytes()); // flags
+            content.extend_from_slice(&(palette.len() as u16 - 1).to_be_bytes());
+            for (i, color) in palette.iter().enumerate() {
+                content.extend_from_slice(&(i as u16).to_be_bytes());
+                for c in color {
+                    content.extend_from_slice(&[*c, *c]);
+                }
+            }
+        }
+        content.extend(rect(0, 0, height, width));
+        content.extend(rect(0, 0, height, width));
+        content.extend_from_slice(&0u16.to_be_bytes()); // mode
+
+        for row in rows.chunks(row_bytes as usize) {
+            if packed && row_bytes >= 8 {
+                content.push(row.len() as u8 + 1);
+                content.push(row.len() as u8 - 1);
+            }
+            content.extend_from_slice(row);
+        }
+
+        self.op(if packed { 0x0098 } else { 0x0090 }, &content)
+    }
+
+    /// Append a CompressedQuickTime with an identity matrix, dimensions are width, height and depth
+    pub fn compressed_quicktime(
+        self,
+        codec: &[u8; 4],
+        (width, height, depth): (u16, u16, u16),
+        source: (u16, u16, u16, u16),
+        data: &[u8],
+    ) -> Self {
+        let identity = [1 << 16, 0, 0, 0, 1 << 16, 0, 0, 0, 1 << 30];
+
+        self.transformed_quicktime(codec, (width, height, depth), source, (identity, 0), data)
+    }
+
+    /// Append a CompressedQuickTime mapped by the matrix, decoded with the given accuracy
+    pub fn transformed_quicktime(
+        self,
+        codec: &[u8; 4],
+        (width, height, depth): (u16, u16, u16),
+        (top, left, bottom, right): (u16, u16, u16, u16),
+        (matrix, accuracy): ([i32; 9], u32),
+        data: &[u8],
+    ) -> Self {
+        let mut content = Vec::new();
+        content.extend_from_slice(&((68 + 86 + data.len() + data.len() % 2) as u32).to_be_bytes());
+        content.extend_from_slice(&0u16.to_be_bytes()); // version
+        for value in matrix {
+            content.extend_from_slice(&value.to_be_bytes());
+        }
+        content.extend_from_slice(&0u32.to_be_bytes()); // matte size
+        content.extend(rect(0, 0, 0, 0));
+        content.extend_from_slice(&0u16.to_be_bytes()); // mode
+        content.extend(rect(top, left, bottom, right));
+        content.extend_from_slice(&accuracy.to_be_bytes());
+        content.extend_from_slice(&0u32.to_be_bytes()); // mask size
+
+        content.extend_from_slice(&86u32.to_be_bytes());
+        content.extend_from_slice(codec);
+        content.extend_from_slice(&[0; 8]); // reserved
+        content.extend_from_slice(&[0; 2 + 2 + 4 + 4 + 4]); // version to spatial quality
+        content.extend_from_slice(&width.to_be_bytes());
+        content.extend_from_slice(&height.to_be_bytes());
+        content.extend_from_slice(&(72u32 << 16).to_be_bytes());
+        content.extend_from_slice(&(72u32 << 16).to_be_bytes());
+        content.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        content.extend_from_slice(&1u16.to_be_bytes()); // frame count
+        content.extend_from_slice(&[0; 32]); // name
+        content.extend_from_slice(&depth.to_be_bytes());
+        content.extend_from_slice(&(-1i16).to_be_bytes()); // color table
+        content.extend_from_slice(data);
+
+        self.op(0x8200, &content)
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.op(0x00FF, &[]).0
+    }
+}
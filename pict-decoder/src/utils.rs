@@ -12,8 +12,8 @@ pub fn ensure_remains_bytes(buf: impl Buf, amount: usize) -> Result<impl Buf> {
     Ok(buf.take(amount))
 }
 
-pub fn skip_filler(mut buf: impl Buf) -> Result<()> {
-    let fill = buf.get_u8();
+pub fn skip_filler(buf: impl Buf) -> Result<()> {
+    let fill = ensure_remains_bytes(buf, 1)?.get_u8();
     if fill != 0 {
         return Err(Error::InvalidFiller(fill));
     }